@@ -20,4 +20,6 @@ pub mod day_19;
 pub mod day_20;
 pub mod day_21;
 pub mod day_22;
-pub mod day_23;
\ No newline at end of file
+pub mod day_23;
+pub mod day_24;
+pub mod day_25;
\ No newline at end of file
@@ -1,82 +1,152 @@
 pub mod solutions {
-    use std::collections::HashSet;
-
-    use itertools::Itertools;
+    use std::collections::{HashSet, VecDeque};
 
     use crate::AocBufReader;
+    use crate::utils::automaton::{Automaton, Cycle, Simulation};
+    use crate::utils::grid::Grid;
+
+    /// Give up looking for a step where every octopus flashes after this many
+    const MAX_STEPS_TO_SYNC: usize = 100_000;
+    const FLASH_THRESHOLD: usize = 9;
+
+
+    type Octopus = (usize, usize);
+
+
+    /// The energy level of every octopus, without any record of past steps,
+    /// so that repeated arrangements compare equal
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    struct EnergyLevels {
+        grid: Grid<usize>,
+    }
+
+
+    impl EnergyLevels {
+        /// Advances one step and returns its flashes in waves: first the
+        /// octopi pushed past the threshold by the step itself, then those
+        /// pushed past it by the previous wave, and so on
+        fn flash(&mut self) -> Vec<Vec<Octopus>> {
+            let mut flashed: HashSet<Octopus> = HashSet::new();
+            let mut to_flash: VecDeque<(Octopus, usize)> = VecDeque::new();
+            for (row, col) in self.grid.coordinates() {
+                let energy = self.grid.get(row, col) + 1;
+                self.grid.set(row, col, energy);
+                if energy > FLASH_THRESHOLD {
+                    flashed.insert((row, col));
+                    to_flash.push_back(((row, col), 0));
+                }
+            }
+
+            let mut waves: Vec<Vec<Octopus>> = vec![];
+            while let Some(((row, col), wave)) = to_flash.pop_front() {
+                if waves.len() == wave {
+                    waves.push(vec![]);
+                }
+                waves[wave].push((row, col));
+                for (adj_row, adj_col) in self.grid.neighbors(row, col, true) {
+                    let energy = self.grid.get(adj_row, adj_col) + 1;
+                    self.grid.set(adj_row, adj_col, energy);
+                    if energy > FLASH_THRESHOLD && flashed.insert((adj_row, adj_col)) {
+                        to_flash.push_back(((adj_row, adj_col), wave + 1));
+                    }
+                }
+            }
+
+            for (row, col) in flashed.iter() {
+                self.grid.set(*row, *col, 0);
+            }
+            waves
+        }
+    }
+
+
+    impl Automaton for EnergyLevels {
+        fn step(&mut self) {
+            self.flash();
+        }
+    }
 
 
     struct Octopi {
-        grid: Vec<Vec<usize>>,
+        levels: EnergyLevels,
         n_flashes: usize,
         // Did all of the octopi flash this round?
-        nsync: bool // Bye, Bye, Bye; bonus for dereferencing this val *nsync
+        nsync: bool, // Bye, Bye, Bye; bonus for dereferencing this val *nsync
+        // history[step_idx] holds that step's flashes, wave by wave
+        history: Vec<Vec<Vec<Octopus>>>,
     }
 
 
     impl Octopi {
-        fn from_reader(aoc_reader: AocBufReader) -> Octopi {
-            let mut grid: Vec<Vec<usize>> = Vec::new();
-            for row in aoc_reader {
-                grid.push(row.chars().map(|c| c.to_digit(10).unwrap() as usize).collect());
-            }
+        fn from_lines<I: IntoIterator<Item = String>>(lines: I) -> Octopi {
+            let grid = Grid::from_lines(lines, |c| c.to_digit(10).unwrap() as usize);
+            Octopi { levels: EnergyLevels { grid }, n_flashes: 0, nsync: false, history: vec![] }
+        }
 
-            Octopi {grid: grid, n_flashes: 0, nsync: false}
+        fn from_reader(aoc_reader: AocBufReader) -> Octopi {
+            Octopi::from_lines(aoc_reader)
         }
 
-        fn get_adjacent(&mut self, row_idx: usize,  col_idx: usize) -> Vec<(usize, usize)> {
-            (-1isize..=1).cartesian_product(-1isize..=1).filter(|(drow, dcol)| {
-                !(*drow == 0 && *dcol ==0)
-            }).map(
-                |(drow, dcol)| (row_idx as isize + drow, col_idx as isize + dcol)
-            ).filter(
-                |(new_row, new_col)| {
-                    (*new_row >= 0 && *new_row < 10) && (*new_col >= 0 && *new_col < 10)
-                }
-            ).map(|(new_row, new_col)| (new_row as usize, new_col as usize)).collect()
+        fn n_octopi(&self) -> usize {
+            self.levels.grid.n_rows() * self.levels.grid.n_cols()
         }
 
         fn step(&mut self) {
-            self.nsync = false;
-
-            let mut octopi_to_step: Vec<(usize, usize)> = vec![];
-            let mut flashed: HashSet<(usize, usize)> = HashSet::new();
-            let mut val: usize;
-
-            for row_idx in 0..self.grid.len() {
-                for col_idx in 0..self.grid.len() {
-                    octopi_to_step.push((row_idx, col_idx));
-                    while octopi_to_step.len() > 0 {
-                        let curr_coord = octopi_to_step.pop().unwrap();
-                        if flashed.contains(&curr_coord) {continue}
-                        match curr_coord {  // Rust doesn't support destructuring tuples...?
-                            (cur_row, cur_col) => {
-                                val = self.grid[cur_row][cur_col];
-                                if val == 9 {
-                                    self.grid[cur_row][cur_col] = 0;
-                                    self.n_flashes += 1;
-                                    flashed.insert((cur_row, cur_col));
-                                    octopi_to_step.extend(
-                                        self.get_adjacent(cur_row, cur_col).iter().filter(|coord| {!flashed.contains(coord)})
-                                    );
-                                } else {
-                                    self.grid[cur_row][cur_col] = val + 1;
-                                }
-                            }
-                        }
-                    }                
+            let waves = self.levels.flash();
+            let n_flashed: usize = waves.iter().map(|wave| wave.len()).sum();
+            self.n_flashes += n_flashed;
+            self.nsync = n_flashed == self.n_octopi();  // It's Gonna Be Me
+            self.history.push(waves);
+        }
+
+        /// Every octopus that flashed during step step_idx (counting from zero)
+        fn flashes(&self, step_idx: usize) -> HashSet<Octopus> {
+            self.propagation_order(step_idx).into_iter().collect()
+        }
+
+        /// The octopi that flashed during step step_idx, in the order the flashes spread
+        fn propagation_order(&self, step_idx: usize) -> Vec<Octopus> {
+            self.history[step_idx].iter().flatten().copied().collect()
+        }
+
+        /// Steps until every octopus flashes at once, then keeps stepping until
+        /// the energy levels repeat. Only states from the synchronizing step on
+        /// are compared, so the cycle found never starts before that step, even
+        /// if the same levels turned up earlier.
+        fn sync_cycle(&mut self, max_steps: usize) -> Option<Cycle> {
+            let sync_step = Simulation::new(self).run_until(|octopi| octopi.nsync, max_steps)?;
+            let mut levels = self.levels.clone();
+            let cycle = Simulation::new(&mut levels).find_cycle(max_steps)?;
+            Some(Cycle { start: sync_step + cycle.start, period: cycle.period })
+        }
+
+        /// The energy levels, with octopi that flashed on the latest step drawn as `*`
+        fn frame(&self) -> String {
+            let flashed = match self.history.len() {
+                0 => HashSet::new(),
+                n_steps => self.flashes(n_steps - 1),
+            };
+            let mut frame = Grid::new(self.levels.grid.n_rows(), self.levels.grid.n_cols(), '*');
+            for (row, col) in frame.coordinates() {
+                if !flashed.contains(&(row, col)) {
+                    frame.set(row, col, std::char::from_digit(*self.levels.grid.get(row, col) as u32, 10).unwrap());
                 }
             }
-            if flashed.len() == 100 {self.nsync = true;}  // It's Gonna Be Me
+            frame.render(|c| *c)
+        }
+    }
+
+
+    impl Automaton for Octopi {
+        fn step(&mut self) {
+            Octopi::step(self)
         }
     }
 
 
     pub fn part_1(aoc_reader: AocBufReader) -> usize {
         let mut octopi = Octopi::from_reader(aoc_reader);
-        for _ in 0..100 {
-            octopi.step()
-        }
+        Simulation::new(&mut octopi).run(100);
 
         octopi.n_flashes
     }
@@ -84,12 +154,65 @@ pub mod solutions {
 
     pub fn part_2(aoc_reader: AocBufReader) -> usize {
         let mut octopi = Octopi::from_reader(aoc_reader);
-        let mut step_count: usize = 0;
-        while !octopi.nsync {
+        let sync_step = Simulation::new(&mut octopi).run_until(|octopi| octopi.nsync, MAX_STEPS_TO_SYNC);
+        sync_step.expect("the octopi never all flash at once")
+    }
+
+
+    /// Part 2, printing a frame after every step until the octopi synchronize
+    pub fn trace_part_2(aoc_reader: AocBufReader) -> usize {
+        let mut octopi = Octopi::from_reader(aoc_reader);
+        let sync_step = Simulation::new(&mut octopi)
+            .observe(|step, octopi| println!("after step {}:\n{}\n", step, octopi.frame()))
+            .run_until(|octopi| octopi.nsync, MAX_STEPS_TO_SYNC);
+        sync_step.expect("the octopi never all flash at once")
+    }
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn example() -> Octopi {
+            Octopi::from_lines(vec![
+                "5483143223", "2745854711", "5264556173", "6141336146", "6357385478",
+                "4167524645", "2176841721", "6882881134", "4846848554", "5283751526",
+            ].into_iter().map(|line| line.to_string()))
+        }
+
+        #[test]
+        fn test_propagation_order() {
+            let mut octopi = Octopi::from_lines(
+                vec!["11111", "19991", "19191", "19991", "11111"].into_iter().map(|line| line.to_string())
+            );
+            octopi.step();
+            assert_eq!(octopi.frame(), "34543\n4***4\n5***5\n4***4\n34543");
+            assert_eq!(octopi.history[0].iter().map(|wave| wave.len()).collect::<Vec<usize>>(), vec![8, 1]);
+            assert_eq!(octopi.propagation_order(0)[..3], [(1, 1), (1, 2), (1, 3)]);
+            assert_eq!(octopi.propagation_order(0).last(), Some(&(2, 2)));
+
             octopi.step();
-            step_count += 1;
+            assert_eq!(octopi.frame(), "45654\n51115\n61116\n51115\n45654");
+            assert!(octopi.flashes(1).is_empty());
         }
 
-        step_count
+        #[test]
+        fn test_flash_history() {
+            let mut octopi = example();
+            Simulation::new(&mut octopi).run(100);
+            assert_eq!(octopi.n_flashes, 1656);
+            assert_eq!(octopi.flashes(0).len(), 0);
+            assert_eq!(octopi.flashes(1).len(), 35);
+            let n_flashes: usize = (0..100).map(|step_idx| octopi.flashes(step_idx).len()).sum();
+            assert_eq!(n_flashes, 1656);
+        }
+
+        #[test]
+        fn test_sync_cycle() {
+            let mut octopi = example();
+            assert_eq!(octopi.sync_cycle(1000), Some(Cycle { start: 195, period: 10 }));
+            assert!(octopi.nsync);
+            assert_eq!(octopi.frame().chars().filter(|c| *c == '*').count(), 100);
+        }
     }
-}
\ No newline at end of file
+}
@@ -1,145 +1,238 @@
 pub mod solutions {
     use std::collections::HashSet;
-
-    use itertools::Itertools;
-    use lazy_static::lazy_static;
-    use regex::Regex;
+    use std::fmt;
 
     use crate::AocBufReader;
+    use crate::utils::grid::Grid;
+    use crate::utils::netpbm::write_pbm;
+    use crate::utils::ocr::recognize;
+    use crate::utils::parse::scan;
 
-    lazy_static! {
-        static ref FOLD_REGEX: Regex = Regex::new("([x,y])=([0-9]*)$").unwrap();
-    }
-
-    #[derive(Clone, Copy)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     enum Axes {
         X,
         Y,
     }
 
 
+    const IMAGE_PATH: &str = "day_13_pt_2.pbm";
+    const IMAGE_SCALE: usize = 8;
+
+
+    type Mark = (isize, isize);
+    type Fold = (Axes, isize);
+
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum FoldError {
+        // fold_idx counts from zero
+        MarkOnCrease { fold_idx: usize, mark: Mark },
+    }
+
+
+    impl fmt::Display for FoldError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                FoldError::MarkOnCrease { fold_idx, mark: (x_val, y_val) } => {
+                    write!(f, "fold {} creases through the mark at {},{}", fold_idx, x_val, y_val)
+                },
+            }
+        }
+    }
+
+
     struct Paper {
-        marks: HashSet<(isize, isize)>,
-        folds: Vec<(Axes, isize)>
+        marks: HashSet<Mark>,
+        folds: Vec<Fold>
     }
 
 
     impl Paper {
-        fn from_reader(mut aoc_reader: AocBufReader) -> Paper {
-            let mut marks: HashSet<(isize, isize)> = HashSet::new();
-            let mut line: String = aoc_reader.next().unwrap();
-
-            while line.len() > 0 {
-                let coord: Vec<isize> = line.split(",").into_iter().map(|val| val.parse::<isize>().unwrap()).collect();
-                marks.insert((coord[0], coord[1]));
-                line = aoc_reader.next().unwrap()
+        fn from_lines<I: IntoIterator<Item = String>>(lines: I) -> Paper {
+            let mut lines = lines.into_iter();
+            let mut marks: HashSet<Mark> = HashSet::new();
+            for line in lines.by_ref().take_while(|line| !line.is_empty()) {
+                marks.insert(scan::<(isize, isize)>("{},{}", &line).unwrap());
             }
 
-            let mut folds: Vec<(Axes, isize)> = vec![];
-            while let Some(line) = aoc_reader.next() {
-                let cap = FOLD_REGEX.captures(&line).unwrap();
-                match cap.get(1).unwrap().as_str() {
-                    "x" => folds.push((Axes::X, cap.get(2).unwrap().as_str().parse::<isize>().unwrap())),
-                    "y" => folds.push((Axes::Y, cap.get(2).unwrap().as_str().parse::<isize>().unwrap())),
+            let mut folds: Vec<Fold> = vec![];
+            for line in lines {
+                let (axis, crease_val) = scan::<(char, isize)>("fold along {}={}", &line).unwrap();
+                match axis {
+                    'x' => folds.push((Axes::X, crease_val)),
+                    'y' => folds.push((Axes::Y, crease_val)),
                     _ => panic!("unknown AXIS!")
                 }
-
-
             }
 
-            Paper {marks: marks, folds: folds}
+            Paper { marks, folds }
         }
 
         fn _fold_1d(crease: isize, val: isize) -> isize {
             crease + (crease - val)
         }
 
-        fn _fold_along_x(&mut self, fold_x_val: isize) {
-            let mut marks_to_add: HashSet<(isize, isize)> = HashSet::new();
-            let mut marks_to_remove: HashSet<(isize, isize)> = HashSet::new();
+        /// Where a mark lands after the fold
+        fn fold_mark((x_val, y_val): Mark, (axis, crease_val): Fold) -> Mark {
+            match axis {
+                Axes::X if x_val > crease_val => (Paper::_fold_1d(crease_val, x_val), y_val),
+                Axes::Y if y_val > crease_val => (x_val, Paper::_fold_1d(crease_val, y_val)),
+                _ => (x_val, y_val)
+            }
+        }
 
-            for mark in &self.marks {
-                match mark {
-                    (x_val, y_val) => {
-                        if *x_val > fold_x_val {
-                            marks_to_remove.insert(*mark);
-                            marks_to_add.insert((Paper::_fold_1d(fold_x_val, *x_val),  *y_val));
-                        }
-                    }
-                }
+        fn on_crease((x_val, y_val): Mark, (axis, crease_val): Fold) -> bool {
+            match axis {
+                Axes::X => x_val == crease_val,
+                Axes::Y => y_val == crease_val,
             }
-            self.marks = self.marks.difference(&marks_to_remove).map(|x| x.to_owned()).collect();
-            self.marks = self.marks.union(&marks_to_add).map(|x| x.to_owned()).collect();
-        }
-
-        fn _fold_along_y(&mut self, fold_y_val: isize) {
-            let mut marks_to_add: HashSet<(isize, isize)> = HashSet::new();
-            let mut marks_to_remove: HashSet<(isize, isize)> = HashSet::new();
-
-            for mark in &self.marks {
-                match mark {
-                    (x_val, y_val) => {
-                        if *y_val > fold_y_val {
-                            marks_to_remove.insert(*mark);
-                            marks_to_add.insert((*x_val, Paper::_fold_1d(fold_y_val, *y_val)));
-                        }
-                    }
+        }
+
+        /// The marks left after each of folds in turn
+        fn fold_sequence(&self, folds: &[Fold]) -> Result<Vec<HashSet<Mark>>, FoldError> {
+            let mut snapshots: Vec<HashSet<Mark>> = vec![];
+            let mut marks = &self.marks;
+            for (fold_idx, fold) in folds.iter().enumerate() {
+                let mut on_crease: Vec<&Mark> = marks.iter().filter(|mark| Paper::on_crease(**mark, *fold)).collect();
+                on_crease.sort();
+                if let Some(mark) = on_crease.first() {
+                    return Err(FoldError::MarkOnCrease { fold_idx, mark: **mark })
                 }
+                snapshots.push(marks.iter().map(|mark| Paper::fold_mark(*mark, *fold)).collect());
+                marks = snapshots.last().unwrap();
             }
-            self.marks = self.marks.difference(&marks_to_remove).map(|x| x.to_owned()).collect();
-            self.marks = self.marks.union(&marks_to_add).map(|x| x.to_owned()).collect();
+            Ok(snapshots)
         }
 
-        fn fold(&mut self, axis: Axes, crease_val: isize) {
-            match axis {
-                Axes::X => self._fold_along_x(crease_val),
-                Axes::Y => self._fold_along_y(crease_val)
-            }
+        fn fold_all(&self) -> Result<Vec<HashSet<Mark>>, FoldError> {
+            self.fold_sequence(&self.folds)
         }
 
-        fn print(&self) {
-            let max_x = self.marks.iter().fold(isize::MIN, |a, (x, y)| a.max(*x));
-            let max_y = self.marks.iter().fold(isize::MIN, |a, (x, y)| a.max(*y));
+        /// Marked cells true, from the top left corner (or any mark folded past it)
+        fn to_grid(marks: &HashSet<Mark>) -> Grid<bool> {
+            let min_x = marks.iter().map(|(x, _)| *x).min().unwrap_or(0).min(0);
+            let min_y = marks.iter().map(|(_, y)| *y).min().unwrap_or(0).min(0);
+            let max_x = marks.iter().map(|(x, _)| *x).max().unwrap_or(-1);
+            let max_y = marks.iter().map(|(_, y)| *y).max().unwrap_or(-1);
 
-            let mut grid: Vec<Vec<&str>> = vec![];
-            for _ in 0..=max_y {
-                grid.push((0..=max_x).map(|_| ".").collect());
+            let mut grid = Grid::new((max_y - min_y + 1) as usize, (max_x - min_x + 1) as usize, false);
+            for (x_val, y_val) in marks {
+                grid.set((*y_val - min_y) as usize, (*x_val - min_x) as usize, true);
             }
+            grid
+        }
 
-            for mark in &self.marks {
-                match mark {
-                    (x_val, y_val) => grid[*y_val as usize][*x_val as usize] = "#"
-                }
-            }
+        fn render(marks: &HashSet<Mark>) -> String {
+            Paper::to_grid(marks).render(|marked| if *marked { '#' } else { '.' })
+        }
 
-            for row in grid {
-                println!("{}", row.iter().join(""));
-            }
+        /// The unfolded sheet followed by the sheet after each fold
+        fn render_folds(&self) -> Result<Vec<String>, FoldError> {
+            let mut sheets = vec![Paper::render(&self.marks)];
+            sheets.extend(self.fold_all()?.iter().map(Paper::render));
+            Ok(sheets)
+        }
+
+        fn read_code(marks: &HashSet<Mark>) -> String {
+            recognize(&Paper::to_grid(marks)).unwrap_or_else(|err| panic!("could not read the code: {}", err))
         }
     }
 
 
+    fn fold_or_panic(paper: &Paper) -> Vec<HashSet<Mark>> {
+        paper.fold_all().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+
+    fn _part_1(paper: &Paper) -> usize {
+        // with no folds at all, the sheet is left as it is
+        let snapshots = paper.fold_sequence(paper.folds.get(..1).unwrap_or(&[])).unwrap_or_else(|err| panic!("{}", err));
+        snapshots.last().unwrap_or(&paper.marks).len()
+    }
+
+
     pub fn part_1(aoc_reader: AocBufReader) -> usize {
-        let mut paper = Paper::from_reader(aoc_reader);
-        let folds = paper.folds.clone();
-        match folds[0] {
-            (axis, crease_val) => paper.fold(axis, crease_val)
+        _part_1(&Paper::from_lines(aoc_reader))
+    }
+
+
+    pub fn part_2(aoc_reader: AocBufReader) -> String {
+        let paper = Paper::from_lines(aoc_reader);
+        Paper::read_code(fold_or_panic(&paper).last().unwrap())
+    }
+
+
+    /// Part 2, printing the sheet before folding and after every fold
+    pub fn trace_part_2(aoc_reader: AocBufReader) -> String {
+        let paper = Paper::from_lines(aoc_reader);
+        for (idx, sheet) in paper.render_folds().unwrap_or_else(|err| panic!("{}", err)).iter().enumerate() {
+            println!("after {} folds:\n{}\n", idx, sheet);
         }
+        Paper::read_code(fold_or_panic(&paper).last().unwrap())
+    }
+
 
-        paper.marks.len()
+    /// Part 2, also saving the folded sheet as a PBM image
+    pub fn export_part_2(aoc_reader: AocBufReader) -> String {
+        let paper = Paper::from_lines(aoc_reader);
+        let folded = fold_or_panic(&paper).pop().unwrap();
+        write_pbm(IMAGE_PATH, &Paper::to_grid(&folded), IMAGE_SCALE)
+            .unwrap_or_else(|err| panic!("could not write {}: {}", IMAGE_PATH, err));
+        Paper::read_code(&folded)
     }
 
 
-    pub fn part_2(aoc_reader: AocBufReader) -> usize {
-        let mut paper = Paper::from_reader(aoc_reader);
-        let folds = paper.folds.clone();
-        for fold in folds {
-            match fold {
-                (axis, crease_val) => paper.fold(axis, crease_val)
-            }
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const EXAMPLE: &str = "6,10\n0,14\n9,10\n0,3\n10,4\n4,11\n6,0\n6,12\n4,1\n0,13\n10,12\n3,4\n3,0\n8,4\n1,10\n2,14\n8,10\n9,0\n\nfold along y=7\nfold along x=5";
+
+        fn example() -> Paper {
+            Paper::from_lines(EXAMPLE.lines().map(|line| line.to_string()))
+        }
+
+        #[test]
+        fn test_fold_all() {
+            let paper = example();
+            assert_eq!(paper.folds, vec![(Axes::Y, 7), (Axes::X, 5)]);
+
+            let snapshots = paper.fold_all().unwrap();
+            assert_eq!(snapshots.iter().map(|marks| marks.len()).collect::<Vec<usize>>(), vec![17, 16]);
+            assert_eq!(
+                Paper::render(&snapshots[1]),
+                "#####\n#...#\n#...#\n#...#\n#####"
+            );
         }
 
-        paper.print();
-        paper.marks.len()
+        #[test]
+        fn test_fold_sequence() {
+            let paper = example();
+            assert_eq!(paper.fold_sequence(&[]), Ok(vec![]));
+
+            // folding the other way first gives the same sheet
+            let snapshots = paper.fold_sequence(&[(Axes::X, 5), (Axes::Y, 7)]).unwrap();
+            assert_eq!(snapshots[1], paper.fold_all().unwrap()[1]);
+
+            assert_eq!(
+                paper.fold_sequence(&[(Axes::Y, 7), (Axes::Y, 4)]),
+                Err(FoldError::MarkOnCrease { fold_idx: 1, mark: (1, 4) })
+            );
+        }
+
+        #[test]
+        fn test_part_1() {
+            assert_eq!(_part_1(&example()), 17);
+            let unfolded = Paper::from_lines(vec!["1,2".to_string(), "3,4".to_string(), "".to_string()]);
+            assert_eq!(_part_1(&unfolded), 2);
+        }
+
+        #[test]
+        fn test_render_folds() {
+            let sheets = example().render_folds().unwrap();
+            assert_eq!(sheets.len(), 3);
+            assert_eq!(sheets[0].lines().count(), 15);
+            assert_eq!(sheets[1].lines().next(), Some("#.##..#..#."));
+        }
     }
-}
\ No newline at end of file
+}
@@ -1,41 +1,17 @@
 pub mod solutions {
-    use std::collections::{HashMap, HashSet};
+    use std::cmp::{max, min};
     use std::fmt;
 
     use crate::AocBufReader;
-    use crate::utils::conversion::binary_string_slice_to_usize;
-
-    use lazy_static::lazy_static;
-
-
-    lazy_static! {
-        static ref HEX_TO_BITS: HashMap<char, &'static str> = vec![
-            ('0', "0000"),
-            ('1', "0001"),
-            ('2', "0010"),
-            ('3', "0011"),
-            ('4', "0100"),
-            ('5', "0101"),
-            ('6', "0110"),
-            ('7', "0111"),
-            ('8', "1000"),
-            ('9', "1001"),
-            ('A', "1010"),
-            ('B', "1011"),
-            ('C', "1100"),
-            ('D', "1101"),
-            ('E', "1110"),
-            ('F', "1111"),
-        ].into_iter().collect();
-    }
-
-    #[derive(PartialEq, Eq, Debug)]
+
+
+    #[derive(PartialEq, Eq, Debug, Clone, Copy)]
     enum PacketType {
         Literal,
         Operator,
     }
 
-    #[derive(PartialEq, Eq, Debug)]
+    #[derive(PartialEq, Eq, Debug, Clone)]
     struct Packet {
         packet_version: usize,
         packet_type: PacketType,
@@ -57,47 +33,68 @@ pub mod solutions {
             sub_packet_sum + self.packet_version
         }
 
-        fn perform_operations(&self) -> usize {
-            let mut result: usize;
-            let mut subpacket_iterator = self.sub_packets.iter().map(|sp| sp.perform_operations());
+        fn perform_operations(&self) -> Result<usize, OperandError> {
+            if self.packet_type == PacketType::Literal {
+                return Ok(self.literal_val.unwrap())
+            }
+            check_operands(self.packet_type_id, self.sub_packets.len())?;
+
+            let operands = self.sub_packets.iter()
+                .map(|sp| sp.perform_operations())
+                .collect::<Result<Vec<usize>, OperandError>>()?;
+            let result = match self.packet_type_id {
+                0 => operands.iter().sum(),
+                1 => operands.iter().product(),
+                2 => *operands.iter().min().unwrap(),
+                3 => *operands.iter().max().unwrap(),
+                5 => (operands[0] > operands[1]) as usize,
+                6 => (operands[0] < operands[1]) as usize,
+                7 => (operands[0] == operands[1]) as usize,
+                _ => unreachable!("check_operands rejects type ID {}", self.packet_type_id)
+            };
+            Ok(result)
+        }
+
+        fn operator_name(&self) -> &'static str {
+            match self.packet_type_id {
+                0 => "sum",
+                1 => "product",
+                2 => "min",
+                3 => "max",
+                4 => "literal",
+                5 => "gt",
+                6 => "lt",
+                7 => "eq",
+                _ => "unknown"
+            }
+        }
 
+        /// Renders the packet tree as nested calls, e.g. `sum(3, max(7, 8))`
+        fn expression(&self) -> String {
             match self.packet_type {
-                PacketType::Literal => result = self.literal_val.unwrap(),
-                PacketType::Operator => {
-                    match self.packet_type_id {
-                        0 => result = subpacket_iterator.sum(),
-                        1 => result = subpacket_iterator.fold(1, |product, xx| product * xx),
-                        2 => result = subpacket_iterator.min().unwrap(),
-                        3 => result = subpacket_iterator.max().unwrap(),
-                        5 => result = {
-                            let first = subpacket_iterator.next().unwrap();
-                            let second = subpacket_iterator.next().unwrap();
-                            match first > second {
-                                true => 1,
-                                false => 0
-                            }
-                        },
-                        6 => result = {
-                            let first = subpacket_iterator.next().unwrap();
-                            let second = subpacket_iterator.next().unwrap();
-                            match first < second {
-                                true => 1,
-                                false => 0
-                            }
-                        },
-                        7 => result = {
-                            let first = subpacket_iterator.next().unwrap();
-                            let second = subpacket_iterator.next().unwrap();
-                            match first == second {
-                                true => 1,
-                                false => 0
-                            }
-                        },
-                        _ => panic!("Unknown operator!")
-                    }
-                }
+                PacketType::Literal => self.literal_val.unwrap().to_string(),
+                PacketType::Operator => format!(
+                    "{}({})",
+                    self.operator_name(),
+                    self.sub_packets.iter().map(|p| p.expression()).collect::<Vec<String>>().join(", ")
+                )
+            }
+        }
+
+        /// Renders the packet tree with infix arithmetic and comparisons, e.g. `(3 + max(7, 8))`
+        fn infix_expression(&self) -> String {
+            let operands: Vec<String> = self.sub_packets.iter().map(|p| p.infix_expression()).collect();
+            let infix = |symbol: &str| format!("({})", operands.join(&format!(" {} ", symbol)));
+            match (self.packet_type, self.packet_type_id) {
+                (PacketType::Literal, _) => self.literal_val.unwrap().to_string(),
+                (_, _) if operands.len() == 1 => operands[0].clone(),
+                (_, 0) => infix("+"),
+                (_, 1) => infix("*"),
+                (_, 5) => infix(">"),
+                (_, 6) => infix("<"),
+                (_, 7) => infix("=="),
+                _ => format!("{}({})", self.operator_name(), operands.join(", "))
             }
-            result
         }
     }
 
@@ -114,87 +111,381 @@ pub mod solutions {
     }
 
 
-    fn stream_from_hex(s: &str) -> String {
-        let mut bits: String = String::new();
-        for c in s.chars() {
-            bits.push_str(HEX_TO_BITS.get(&c).unwrap());
+    /// Why an operator packet cannot be evaluated
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum OperandError {
+        /// No operator is defined for this type ID
+        InvalidTypeId { type_id: usize },
+        /// Comparisons take exactly two operands; everything else at least one
+        WrongOperandCount { type_id: usize, found: usize },
+    }
+
+
+    impl fmt::Display for OperandError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                OperandError::InvalidTypeId { type_id } => write!(f, "invalid operator type ID {}", type_id),
+                OperandError::WrongOperandCount { type_id, found } => {
+                    write!(f, "operator type ID {} given {} operands", type_id, found)
+                },
+            }
+        }
+    }
+
+
+    fn check_operands(type_id: usize, found: usize) -> Result<(), OperandError> {
+        let valid = match type_id {
+            0..=3 => found >= 1,
+            5..=7 => found == 2,
+            _ => return Err(OperandError::InvalidTypeId { type_id })
+        };
+        match valid {
+            true => Ok(()),
+            false => Err(OperandError::WrongOperandCount { type_id, found })
         }
-        bits
     }
 
 
-    fn read_bits<'a>(stream: &'a str, cursor: &mut usize, n_bits: usize) -> &'a str {
-        let range_start: usize = *cursor;
-        *cursor += n_bits;
-        &stream[range_start..range_start + n_bits]
+    /// Why a transmission could not be decoded; every offset is in bits from
+    /// the start of the transmission
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum DecodeError {
+        /// A field of n_bits starting at offset runs past the end of the
+        /// transmission, or of the sub-packet region it sits in
+        UnexpectedEnd { offset: usize, n_bits: usize },
+        /// The hex digit encoding the bits at offset is not a hex digit
+        InvalidHexDigit { offset: usize, digit: char },
+        /// The operator packet starting at offset has an unknown type ID
+        InvalidTypeId { offset: usize, type_id: usize },
+        /// The operator packet starting at offset has the wrong number of sub-packets
+        WrongOperandCount { offset: usize, type_id: usize, found: usize },
+        /// The padding after the outermost packet has a 1 bit at offset
+        NonZeroPadding { offset: usize },
+        /// The literal group starting at offset takes the value past the width of a usize
+        LiteralOverflow { offset: usize },
     }
 
 
-    fn decode_literal_packet<'a>(stream: &'a str, cursor: &mut usize, packet_version: usize, packet_type_id: usize) -> Packet {
+    impl DecodeError {
+        fn from_operand_error(err: OperandError, offset: usize) -> DecodeError {
+            match err {
+                OperandError::InvalidTypeId { type_id } => DecodeError::InvalidTypeId { offset, type_id },
+                OperandError::WrongOperandCount { type_id, found } => {
+                    DecodeError::WrongOperandCount { offset, type_id, found }
+                },
+            }
+        }
+    }
+
+
+    impl fmt::Display for DecodeError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                DecodeError::UnexpectedEnd { offset, n_bits } => {
+                    write!(f, "bit {}: stream ends before a {}-bit field", offset, n_bits)
+                },
+                DecodeError::InvalidHexDigit { offset, digit } => write!(f, "bit {}: invalid hex digit {:?}", offset, digit),
+                DecodeError::InvalidTypeId { offset, type_id } => {
+                    write!(f, "bit {}: {}", offset, OperandError::InvalidTypeId { type_id: *type_id })
+                },
+                DecodeError::WrongOperandCount { offset, type_id, found } => {
+                    write!(f, "bit {}: {}", offset, OperandError::WrongOperandCount { type_id: *type_id, found: *found })
+                },
+                DecodeError::NonZeroPadding { offset } => write!(f, "bit {}: non-zero padding", offset),
+                DecodeError::LiteralOverflow { offset } => write!(f, "bit {}: literal value overflows", offset),
+            }
+        }
+    }
+
+
+    /// Reads big-endian bit fields of any width straight out of a byte slice.
+    /// A reader may be limited to fewer bits than the slice holds so that a
+    /// sub-packet region can be decoded without copying it out.
+    struct BitReader<'a> {
+        bytes: &'a [u8],
+        position: usize,
+        end: usize,
+    }
+
+
+    impl<'a> BitReader<'a> {
+        fn new(bytes: &'a [u8]) -> BitReader<'a> {
+            BitReader::with_len(bytes, 8 * bytes.len())
+        }
+
+        fn with_len(bytes: &'a [u8], n_bits: usize) -> BitReader<'a> {
+            BitReader { bytes, position: 0, end: n_bits }
+        }
+
+        fn position(&self) -> usize {
+            self.position
+        }
+
+        fn remaining(&self) -> usize {
+            self.end - self.position
+        }
+
+        /// Reads the next n_bits (at most the width of a usize) as an unsigned integer
+        fn read(&mut self, n_bits: usize) -> Result<usize, DecodeError> {
+            if n_bits > self.remaining() {
+                return Err(DecodeError::UnexpectedEnd { offset: self.position, n_bits })
+            }
+            let mut val: usize = 0;
+            let mut n_left = n_bits;
+            while n_left > 0 {
+                let byte = self.bytes[self.position / 8] as usize;
+                let bit_offset = self.position % 8;
+                let n_take = min(8 - bit_offset, n_left);
+                let chunk = (byte >> (8 - bit_offset - n_take)) & ((1 << n_take) - 1);
+                val = (val << n_take) | chunk;
+                self.position += n_take;
+                n_left -= n_take;
+            }
+            Ok(val)
+        }
+
+        /// A reader over the next n_bits; self skips past them
+        fn split_off(&mut self, n_bits: usize) -> Result<BitReader<'a>, DecodeError> {
+            if n_bits > self.remaining() {
+                return Err(DecodeError::UnexpectedEnd { offset: self.position, n_bits })
+            }
+            let sub_reader = BitReader { bytes: self.bytes, position: self.position, end: self.position + n_bits };
+            self.position += n_bits;
+            Ok(sub_reader)
+        }
+
+        /// The offset of the first 1 bit left unread, if any
+        fn first_set_bit(&self) -> Option<usize> {
+            (self.position..self.end).find(|offset| self.bytes[offset / 8] >> (7 - offset % 8) & 1 == 1)
+        }
+    }
+
+
+    /// Packs bit fields into bytes, most significant bit first
+    struct BitWriter {
+        bytes: Vec<u8>,
+        n_bits: usize,
+    }
+
+
+    impl BitWriter {
+        fn new() -> BitWriter {
+            BitWriter { bytes: vec![], n_bits: 0 }
+        }
+
+        fn len(&self) -> usize {
+            self.n_bits
+        }
+
+        fn write(&mut self, val: usize, n_bits: usize) {
+            assert!(n_bits >= usize::BITS as usize || val >> n_bits == 0, "{} does not fit in {} bits", val, n_bits);
+            for shift in (0..n_bits).rev() {
+                if self.n_bits.is_multiple_of(8) {
+                    self.bytes.push(0);
+                }
+                let bit = ((val >> shift) & 1) as u8;
+                *self.bytes.last_mut().unwrap() |= bit << (7 - self.n_bits % 8);
+                self.n_bits += 1;
+            }
+        }
+
+        fn append(&mut self, other: &BitWriter) {
+            for offset in 0..other.n_bits {
+                self.write((other.bytes[offset / 8] >> (7 - offset % 8) & 1) as usize, 1);
+            }
+        }
+
+        /// Hex digits for every byte written so far; the last byte is zero-padded
+        fn to_hex(&self) -> String {
+            self.bytes.iter().map(|byte| format!("{:02X}", byte)).collect()
+        }
+    }
+
+
+    fn bytes_from_hex(s: &str) -> Result<Vec<u8>, DecodeError> {
+        let digits = s.trim().chars().enumerate().map(|(idx, c)| match c.to_digit(16) {
+            Some(digit) => Ok(digit as u8),
+            None => Err(DecodeError::InvalidHexDigit { offset: 4 * idx, digit: c })
+        }).collect::<Result<Vec<u8>, DecodeError>>()?;
+        Ok(digits.chunks(2).map(|pair| (pair[0] << 4) | pair.get(1).unwrap_or(&0)).collect())
+    }
+
+
+    fn decode_literal_packet(
+        reader: &mut BitReader, packet_version: usize, packet_type_id: usize
+    ) -> Result<Packet, DecodeError> {
         let mut packet = Packet::new(packet_version, PacketType::Literal, packet_type_id);
 
-        let mut group: &str;
-        let mut literal_val = String::new();
+        let mut literal_val: usize = 0;
         loop {
-            group = read_bits(stream, cursor, 5);
-            literal_val.push_str(&group[1..]);
-            if group.as_bytes()[0] as char == '0' { break }
+            let offset = reader.position();
+            let group = reader.read(5)?;
+            if literal_val >> (usize::BITS - 4) != 0 {
+                return Err(DecodeError::LiteralOverflow { offset })
+            }
+            literal_val = (literal_val << 4) | (group & 0xF);
+            if group >> 4 == 0 { break }
         }
-        packet.literal_val = Some(binary_string_slice_to_usize(&literal_val));
-        packet
+        packet.literal_val = Some(literal_val);
+        Ok(packet)
     }
 
 
-    fn decode_operator_packet<'a>(stream: &'a str, mut cursor: &mut usize, packet_version: usize, packet_type_id: usize) -> Packet {
+    fn decode_operator_packet(
+        reader: &mut BitReader, packet_version: usize, packet_type_id: usize
+    ) -> Result<Packet, DecodeError> {
         let mut packet = Packet::new(packet_version, PacketType::Operator, packet_type_id);
 
-        let length_type_id: &str = read_bits(stream, cursor, 1);
-        match length_type_id {
-            "0" => {
-                let length: usize = binary_string_slice_to_usize(read_bits(stream, &mut cursor, 15));
-                packet.sub_packets.extend(
-                    decode_packets(&stream[0..*cursor + length], &mut cursor)
-                )
+        match reader.read(1)? {
+            0 => {
+                let length: usize = reader.read(TOTAL_LENGTH_BITS)?;
+                packet.sub_packets.extend(decode_packets(&mut reader.split_off(length)?)?)
             },
-            "1" => {
-                let n_sub_packets: usize = binary_string_slice_to_usize(read_bits(stream, &mut cursor, 11));
+            _ => {
+                let n_sub_packets: usize = reader.read(SUB_PACKET_COUNT_BITS)?;
                 for _ in 0..n_sub_packets {
-                    packet.sub_packets.push(decode_packet(&stream, &mut cursor))
+                    packet.sub_packets.push(decode_packet(reader)?)
                 }
             },
-            _ => panic!("I thought I saw a 2!") // https://www.youtube.com/watch?v=MOn_ySghN2Y
         }
 
-        packet
+        Ok(packet)
     }
 
 
-    fn decode_packet<'a>(stream: &'a str, mut cursor: &mut usize) -> Packet {
-        let packet_version: usize = binary_string_slice_to_usize(read_bits(stream, &mut cursor, 3));
-        let packet_type_id: usize = binary_string_slice_to_usize(read_bits(stream, &mut cursor, 3));
-        match packet_type_id {
-            4 => return decode_literal_packet(stream, &mut cursor, packet_version, packet_type_id),
-            _ => return decode_operator_packet(stream, &mut cursor, packet_version, packet_type_id)
+    fn decode_packet(reader: &mut BitReader) -> Result<Packet, DecodeError> {
+        let offset = reader.position();
+        let packet_version: usize = reader.read(3)?;
+        let packet_type_id: usize = reader.read(3)?;
+        if packet_type_id == 4 {
+            return decode_literal_packet(reader, packet_version, packet_type_id)
         }
+        let packet = decode_operator_packet(reader, packet_version, packet_type_id)?;
+        check_operands(packet_type_id, packet.sub_packets.len())
+            .map_err(|err| DecodeError::from_operand_error(err, offset))?;
+        Ok(packet)
     }
 
 
-    fn decode_packets<'a>(stream: &'a str, mut cursor: &mut usize) -> Vec<Packet> {
+    /// Decodes packets until the reader's bits are used up exactly, as for
+    /// the sub-packets of a length type 0 operator
+    fn decode_packets(reader: &mut BitReader) -> Result<Vec<Packet>, DecodeError> {
         let mut packets: Vec<Packet> = vec![];
 
-        while *cursor < stream.len() && stream[*cursor..].chars().collect::<HashSet<char>>().contains(&'1') {
-            packets.push(decode_packet(stream, &mut cursor));
+        while reader.remaining() > 0 {
+            packets.push(decode_packet(reader)?);
+        }
+        Ok(packets)
+    }
+
+
+    /// Decodes the outermost packet, which may only be followed by zero padding
+    fn decode_transmission(hex_stream: &str) -> Result<Packet, DecodeError> {
+        let bytes = bytes_from_hex(hex_stream)?;
+        let mut reader = BitReader::new(&bytes);
+        let packet = decode_packet(&mut reader)?;
+        match reader.first_set_bit() {
+            Some(offset) => Err(DecodeError::NonZeroPadding { offset }),
+            None => Ok(packet)
+        }
+    }
+
+
+    fn decode_or_panic(hex_stream: &str) -> Packet {
+        decode_transmission(hex_stream).unwrap_or_else(|err| panic!("invalid transmission: {}", err))
+    }
+
+
+    /// How the encoder picks each operator packet's length type ID
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum LengthTypeStrategy {
+        /// Give the total bit length of the sub-packets (type 0) unless it doesn't fit
+        TotalLength,
+        /// Give the number of sub-packets (type 1) unless it doesn't fit
+        SubPacketCount,
+    }
+
+
+    /// Why a packet tree cannot be encoded
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum EncodeError {
+        /// An operator has too many sub-packets to count and too many bits of them to measure
+        SubPacketsTooLarge { n_sub_packets: usize, n_bits: usize },
+    }
+
+
+    impl fmt::Display for EncodeError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                EncodeError::SubPacketsTooLarge { n_sub_packets, n_bits } => write!(
+                    f, "{} sub-packets in {} bits fit neither length field", n_sub_packets, n_bits
+                ),
+            }
+        }
+    }
+
+
+    const TOTAL_LENGTH_BITS: usize = 15;
+    const SUB_PACKET_COUNT_BITS: usize = 11;
+
+
+    /// Splits the literal into 4-bit groups, each prefixed with 1 except the last
+    fn encode_literal_val(writer: &mut BitWriter, literal_val: usize) {
+        let n_groups = max(1, (usize::BITS - literal_val.leading_zeros() + 3) as usize / 4);
+        for group_idx in (0..n_groups).rev() {
+            writer.write((group_idx > 0) as usize, 1);
+            writer.write((literal_val >> (4 * group_idx)) & 0xF, 4);
+        }
+    }
+
+
+    /// Encodes packet, falling back to the other length type wherever the
+    /// strategy's choice of field is too narrow
+    fn encode_packet(packet: &Packet, strategy: LengthTypeStrategy, writer: &mut BitWriter) -> Result<(), EncodeError> {
+        writer.write(packet.packet_version, 3);
+        writer.write(packet.packet_type_id, 3);
+        if packet.packet_type == PacketType::Literal {
+            encode_literal_val(writer, packet.literal_val.unwrap());
+            return Ok(())
+        }
+
+        let mut sub_writer = BitWriter::new();
+        for sub_packet in packet.sub_packets.iter() {
+            encode_packet(sub_packet, strategy, &mut sub_writer)?;
+        }
+
+        let fits_count = packet.sub_packets.len() < 1 << SUB_PACKET_COUNT_BITS;
+        let fits_length = sub_writer.len() < 1 << TOTAL_LENGTH_BITS;
+        let use_count = match (fits_count, fits_length) {
+            (false, false) => return Err(EncodeError::SubPacketsTooLarge {
+                n_sub_packets: packet.sub_packets.len(), n_bits: sub_writer.len()
+            }),
+            (true, false) => true,
+            (false, true) => false,
+            (true, true) => strategy == LengthTypeStrategy::SubPacketCount,
+        };
+        if use_count {
+            writer.write(1, 1);
+            writer.write(packet.sub_packets.len(), SUB_PACKET_COUNT_BITS);
+        } else {
+            writer.write(0, 1);
+            writer.write(sub_writer.len(), TOTAL_LENGTH_BITS);
         }
-        *cursor = stream.len(); // fast forward
-        packets
+        writer.append(&sub_writer);
+        Ok(())
+    }
+
+
+    /// Encodes packet as a hex transmission, zero-padded to whole bytes like the puzzle's examples
+    fn encode_transmission(packet: &Packet, strategy: LengthTypeStrategy) -> Result<String, EncodeError> {
+        let mut writer = BitWriter::new();
+        encode_packet(packet, strategy, &mut writer)?;
+        Ok(writer.to_hex())
     }
 
 
     fn _part_1(hex_stream: &str) -> usize {
-        let stream= &stream_from_hex(hex_stream)[..];
-        let mut cursor: usize = 0;
-        let packets = decode_packets(stream, &mut cursor);
-        packets.iter().map(|p| p.sum_all_versions()).sum()
+        decode_or_panic(hex_stream).sum_all_versions()
     }
 
 
@@ -204,10 +495,8 @@ pub mod solutions {
 
 
     fn _part_2(hex_stream: &str) -> usize {
-        let stream= &stream_from_hex(hex_stream)[..];
-        let mut cursor: usize = 0;
-        let packets = decode_packets(stream, &mut cursor);
-        packets.iter().map(|p| p.perform_operations()).sum()
+        // the decoder has already checked every operator's operands
+        decode_or_panic(hex_stream).perform_operations().unwrap()
     }
 
 
@@ -216,36 +505,75 @@ pub mod solutions {
     }
 
 
+    /// The outermost packet's expression, its infix form and what it evaluates to
+    fn explain(hex_stream: &str) -> String {
+        let packet = decode_or_panic(hex_stream);
+        format!("{}\n  = {}\n  = {}", packet.expression(), packet.infix_expression(), packet.perform_operations().unwrap())
+    }
+
+
+    /// Part 2, printing the decoded expression alongside its value
+    pub fn explain_part_2(mut aoc_reader: AocBufReader) -> usize {
+        let hex_stream = aoc_reader.next().unwrap();
+        println!("{}", explain(&hex_stream));
+        _part_2(&hex_stream)
+    }
+
+
     #[cfg(test)]
     mod tests {
+        use crate::utils::random::Lcg;
         use super::*;
 
+        /// Packs a string of '0'/'1' chars into bytes, zero-padding the last one
+        fn bytes_from_bits(bits: &str) -> Vec<u8> {
+            let mut writer = BitWriter::new();
+            for c in bits.chars() {
+                writer.write(c.to_digit(2).unwrap() as usize, 1);
+            }
+            writer.bytes
+        }
+
+
         #[test]
         fn test_from_hex() {
-            assert_eq!(stream_from_hex("D2FE28"), "110100101111111000101000".to_string());
+            assert_eq!(bytes_from_hex("D2FE28"), Ok(vec![0xD2, 0xFE, 0x28]));
+            assert_eq!(bytes_from_hex("D2FE28"), Ok(bytes_from_bits("110100101111111000101000")));
+            assert_eq!(bytes_from_hex("D2F"), Ok(vec![0xD2, 0xF0]));
+            assert_eq!(bytes_from_hex("D2G"), Err(DecodeError::InvalidHexDigit { offset: 8, digit: 'G' }));
         }
 
 
         #[test]
         fn test_read_bits() {
-            let test_str: &'static str = "abcdefg";
-            let mut cursor: usize = 0;
-            assert_eq!(read_bits(test_str, &mut cursor, 0), "");
-            assert_eq!(cursor, 0);
-
-            assert_eq!(read_bits(test_str, &mut cursor, 2), "ab");
-            assert_eq!(cursor, 2);
-
-            assert_eq!(read_bits(test_str, &mut cursor, 3), "cde");
-            assert_eq!(cursor, 5);
+            let bytes = bytes_from_bits("110100101111111000101000");
+            let mut reader = BitReader::new(&bytes);
+            assert_eq!(reader.read(0), Ok(0));
+            assert_eq!(reader.position(), 0);
+
+            assert_eq!(reader.read(3), Ok(0b110));
+            assert_eq!(reader.read(3), Ok(0b100));
+            assert_eq!(reader.position(), 6);
+
+            // crosses two byte boundaries
+            assert_eq!(reader.read(13), Ok(0b1011111110001));
+            assert_eq!(reader.remaining(), 5);
+
+            let mut sub_reader = reader.split_off(3).unwrap();
+            assert_eq!(reader.remaining(), 2);
+            assert_eq!(sub_reader.read(3), Ok(0b010));
+            assert_eq!(sub_reader.read(1), Err(DecodeError::UnexpectedEnd { offset: 22, n_bits: 1 }));
+            assert_eq!(reader.first_set_bit(), None);
+            assert_eq!(reader.split_off(3).err(), Some(DecodeError::UnexpectedEnd { offset: 22, n_bits: 3 }));
         }
 
 
         #[test]
         fn test_decode_literal_packet() {
-            let stream: &'static str = "110100101111111000101000";
-            let mut cursor: usize = 6;
-            let obs_packet = decode_literal_packet(stream, &mut cursor, 6, 4);
+            let bytes = bytes_from_bits("110100101111111000101000");
+            let mut reader = BitReader::new(&bytes);
+            reader.read(6).unwrap();
+            let obs_packet = decode_literal_packet(&mut reader, 6, 4).unwrap();
             let mut exp_packet = Packet::new(6, PacketType::Literal, 4);
             exp_packet.literal_val = Some(2021usize);
             assert_eq!(obs_packet, exp_packet);
@@ -253,9 +581,8 @@ pub mod solutions {
 
         #[test]
         fn test_decode_packet_literal() {
-            let stream: &'static str = "110100101111111000101000";
-            let mut cursor: usize = 0;
-            let obs_packet = decode_packet(stream, &mut cursor);
+            let bytes = bytes_from_bits("110100101111111000101000");
+            let obs_packet = decode_packet(&mut BitReader::new(&bytes)).unwrap();
             let mut exp_packet = Packet::new(6, PacketType::Literal, 4);
             exp_packet.literal_val = Some(2021usize);
             assert_eq!(obs_packet, exp_packet);
@@ -264,22 +591,26 @@ pub mod solutions {
 
         #[test]
         fn test_decode_packets_literal() {
-            let stream: &'static str = "110100101111111000101000";
-            let mut cursor: usize = 0;
-            let obs_packet = decode_packets(stream, &mut cursor);
+            let bytes = bytes_from_bits("110100101111111000101000");
+            let mut reader = BitReader::new(&bytes);
+            let mut region = reader.split_off(21).unwrap();
+            let obs_packet = decode_packets(&mut region);
             let mut exp_packet = Packet::new(6, PacketType::Literal, 4);
             exp_packet.literal_val = Some(2021usize);
-            assert_eq!(obs_packet, vec![exp_packet]);
-            assert_eq!(cursor, stream.len());
+            assert_eq!(obs_packet, Ok(vec![exp_packet]));
+            assert_eq!(region.position(), 21);
+
+            // the trailing padding is too short to hold another packet
+            let mut reader = BitReader::new(&bytes);
+            assert_eq!(decode_packets(&mut reader), Err(DecodeError::UnexpectedEnd { offset: 24, n_bits: 3 }));
         }
 
 
         #[test]
         fn test_decode_operator_packet() {
-            let stream: &'static str = "00111000000000000110111101000101001010010001001000000000";
-            let mut cursor: usize = 0;
+            let bytes = bytes_from_bits("00111000000000000110111101000101001010010001001000000000");
 
-            let obs_packet = decode_packet(stream, &mut cursor);
+            let obs_packet = decode_packet(&mut BitReader::new(&bytes)).unwrap();
             let mut exp_packet = Packet::new(1, PacketType::Operator, 6);
             let mut sub_packet_1 = Packet::new(6, PacketType::Literal, 4);
             sub_packet_1.literal_val = Some(10usize);
@@ -292,6 +623,215 @@ pub mod solutions {
         }
 
 
+        #[test]
+        fn test_decode_large_transmission() {
+            let mut packet = Packet::new(0, PacketType::Operator, 0);
+            for group_idx in 0..50 {
+                let mut group = Packet::new(group_idx % 8, PacketType::Operator, 1);
+                for idx in 0..1000 {
+                    let mut sub_packet = Packet::new(idx % 8, PacketType::Literal, 4);
+                    sub_packet.literal_val = Some(1 + idx % 3);
+                    group.sub_packets.push(sub_packet);
+                }
+                packet.sub_packets.push(group);
+            }
+            let hex_stream = encode_transmission(&packet, LengthTypeStrategy::SubPacketCount).unwrap();
+            assert!(hex_stream.len() > 100_000);
+            assert_eq!(decode_transmission(&hex_stream), Ok(packet));
+        }
+
+
+        #[test]
+        fn test_encode_literal_packet() {
+            let mut packet = Packet::new(6, PacketType::Literal, 4);
+            packet.literal_val = Some(2021usize);
+            assert_eq!(encode_transmission(&packet, LengthTypeStrategy::SubPacketCount), Ok("D2FE28".to_string()));
+
+            packet.literal_val = Some(0usize);
+            let mut writer = BitWriter::new();
+            encode_packet(&packet, LengthTypeStrategy::SubPacketCount, &mut writer).unwrap();
+            assert_eq!(writer.len(), 11);
+            assert_eq!(writer.bytes, bytes_from_bits("11010000000"));
+        }
+
+
+        #[test]
+        fn test_encode_operator_packet() {
+            let mut packet = Packet::new(1, PacketType::Operator, 6);
+            let mut sub_packet_1 = Packet::new(6, PacketType::Literal, 4);
+            sub_packet_1.literal_val = Some(10usize);
+            let mut sub_packet_2 = Packet::new(2, PacketType::Literal, 4);
+            sub_packet_2.literal_val = Some(20usize);
+            packet.sub_packets.extend(vec![sub_packet_1, sub_packet_2]);
+
+            assert_eq!(encode_transmission(&packet, LengthTypeStrategy::TotalLength), Ok("38006F45291200".to_string()));
+            assert_eq!(&encode_transmission(&packet, LengthTypeStrategy::SubPacketCount).unwrap()[..2], "3A");
+        }
+
+
+        #[test]
+        fn test_encode_length_type_fallback() {
+            let mut literal = Packet::new(0, PacketType::Literal, 4);
+            literal.literal_val = Some(1);
+
+            // the outermost length type ID is the seventh bit
+            // 2048 sub-packets overflow the count field but take only 22528 bits
+            let mut many = Packet::new(0, PacketType::Operator, 0);
+            many.sub_packets = vec![literal.clone(); 2048];
+            let hex_stream = encode_transmission(&many, LengthTypeStrategy::SubPacketCount).unwrap();
+            assert_eq!(bytes_from_hex(&hex_stream).unwrap()[0] >> 1 & 1, 0);
+            assert_eq!(decode_transmission(&hex_stream), Ok(many.clone()));
+
+            // 100 groups of 40 literals overflow the length field
+            let mut group = Packet::new(0, PacketType::Operator, 0);
+            group.sub_packets = vec![literal.clone(); 40];
+            let mut long = Packet::new(0, PacketType::Operator, 0);
+            long.sub_packets = vec![group; 100];
+            let hex_stream = encode_transmission(&long, LengthTypeStrategy::TotalLength).unwrap();
+            assert_eq!(bytes_from_hex(&hex_stream).unwrap()[0] >> 1 & 1, 1);
+            assert_eq!(decode_transmission(&hex_stream), Ok(long));
+
+            many.sub_packets = vec![literal; 3000];
+            assert_eq!(
+                encode_transmission(&many, LengthTypeStrategy::SubPacketCount),
+                Err(EncodeError::SubPacketsTooLarge { n_sub_packets: 3000, n_bits: 33000 })
+            );
+        }
+
+
+        fn random_packet(rng: &mut Lcg, depth: usize) -> Packet {
+            let packet_version = rng.next_in(0, 7) as usize;
+            if depth == 0 || rng.next_in(0, 2) == 0 {
+                let mut packet = Packet::new(packet_version, PacketType::Literal, 4);
+                let n_bits = rng.next_in(0, 60) as u32;
+                packet.literal_val = Some((rng.next_u64() % (1u64 << n_bits).max(1)) as usize);
+                return packet
+            }
+
+            let packet_type_id = [0, 1, 2, 3, 5, 6, 7][rng.next_in(0, 6) as usize];
+            let mut packet = Packet::new(packet_version, PacketType::Operator, packet_type_id);
+            let n_sub_packets = match packet_type_id {
+                5..=7 => 2,
+                _ => rng.next_in(1, 4) as usize
+            };
+            for _ in 0..n_sub_packets {
+                packet.sub_packets.push(random_packet(rng, depth - 1));
+            }
+            packet
+        }
+
+
+        #[test]
+        fn test_encode_round_trip() {
+            let mut rng = Lcg::new(16);
+            for _ in 0..200 {
+                let packet = random_packet(&mut rng, 4);
+                for strategy in [LengthTypeStrategy::TotalLength, LengthTypeStrategy::SubPacketCount].iter() {
+                    let hex_stream = encode_transmission(&packet, *strategy).unwrap();
+                    assert_eq!(decode_transmission(&hex_stream), Ok(packet.clone()), "{}", hex_stream);
+                }
+            }
+        }
+
+
+        #[test]
+        fn test_decode_encoded_examples() {
+            for hex_stream in ["8A004A801A8002F478", "620080001611562C8802118E34", "C0015000016115A2E0802F182340"] {
+                let packet = decode_transmission(hex_stream).unwrap();
+                let reencoded = encode_transmission(&packet, LengthTypeStrategy::SubPacketCount).unwrap();
+                assert_eq!(decode_transmission(&reencoded), Ok(packet));
+            }
+        }
+
+
+        #[test]
+        fn test_expression() {
+            let expressions: Vec<(&str, &str, &str)> = vec![
+                ("C200B40A82", "sum(1, 2)", "(1 + 2)"),
+                ("04005AC33890", "product(6, 9)", "(6 * 9)"),
+                ("880086C3E88112", "min(7, 8, 9)", "min(7, 8, 9)"),
+                ("D8005AC2A8F0", "lt(5, 15)", "(5 < 15)"),
+                ("9C0141080250320F1802104A08", "eq(sum(1, 3), product(2, 2))", "((1 + 3) == (2 * 2))"),
+            ];
+            for (hex_stream, expression, infix) in expressions {
+                let packet = decode_transmission(hex_stream).unwrap();
+                assert_eq!(packet.expression(), expression);
+                assert_eq!(packet.infix_expression(), infix);
+            }
+        }
+
+
+        #[test]
+        fn test_explain() {
+            assert_eq!(
+                explain("9C0141080250320F1802104A08"),
+                "eq(sum(1, 3), product(2, 2))\n  = ((1 + 3) == (2 * 2))\n  = 1"
+            );
+        }
+
+
+        #[test]
+        fn test_decode_errors() {
+            // literal 2021 cut off mid-group
+            assert_eq!(decode_transmission("D2FE"), Err(DecodeError::UnexpectedEnd { offset: 16, n_bits: 5 }));
+            // length type 0 claiming 27 bits of sub-packets with only 22 left
+            assert_eq!(decode_transmission("38006F452912"), Err(DecodeError::UnexpectedEnd { offset: 22, n_bits: 27 }));
+            // literal 2021 followed by a stray 1 bit in the padding
+            assert_eq!(decode_transmission("D2FE29"), Err(DecodeError::NonZeroPadding { offset: 23 }));
+            assert_eq!(decode_transmission("D2FE28 "), decode_transmission("D2FE28"));
+
+            // a literal of 17 groups, the first of them 1, needs 65 bits
+            let mut bits = "110100".to_string() + "10001" + &"10000".repeat(15) + "00000";
+            let bytes = bytes_from_bits(&bits);
+            assert_eq!(
+                decode_packet(&mut BitReader::new(&bytes)),
+                Err(DecodeError::LiteralOverflow { offset: 86 })
+            );
+            // 16 groups still fit
+            bits.truncate(86);
+            bits.replace_range(81..82, "0");
+            let mut exp_packet = Packet::new(6, PacketType::Literal, 4);
+            exp_packet.literal_val = Some(1 << 60);
+            assert_eq!(decode_packet(&mut BitReader::new(&bytes_from_bits(&bits))), Ok(exp_packet));
+
+            let mut sub_packet = Packet::new(0, PacketType::Literal, 4);
+            sub_packet.literal_val = Some(1);
+            let mut packet = Packet::new(0, PacketType::Operator, 5);
+            packet.sub_packets = vec![sub_packet.clone(); 3];
+            let hex_stream = encode_transmission(&packet, LengthTypeStrategy::SubPacketCount).unwrap();
+            assert_eq!(
+                decode_transmission(&hex_stream),
+                Err(DecodeError::WrongOperandCount { offset: 0, type_id: 5, found: 3 })
+            );
+
+            let mut outer = Packet::new(0, PacketType::Operator, 0);
+            packet.sub_packets = vec![];
+            packet.packet_type_id = 2;
+            outer.sub_packets = vec![sub_packet.clone(), packet.clone()];
+            let hex_stream = encode_transmission(&outer, LengthTypeStrategy::SubPacketCount).unwrap();
+            assert_eq!(
+                decode_transmission(&hex_stream),
+                Err(DecodeError::WrongOperandCount { offset: 29, type_id: 2, found: 0 })
+            );
+        }
+
+
+        #[test]
+        fn test_perform_operations_errors() {
+            let mut sub_packet = Packet::new(0, PacketType::Literal, 4);
+            sub_packet.literal_val = Some(1);
+            let mut packet = Packet::new(0, PacketType::Operator, 6);
+            packet.sub_packets = vec![sub_packet.clone()];
+            assert_eq!(packet.perform_operations(), Err(OperandError::WrongOperandCount { type_id: 6, found: 1 }));
+
+            packet.sub_packets.push(sub_packet.clone());
+            assert_eq!(packet.perform_operations(), Ok(0));
+
+            packet.packet_type_id = 4;
+            assert_eq!(packet.perform_operations(), Err(OperandError::InvalidTypeId { type_id: 4 }));
+        }
+
+
         #[test]
         fn test_part_1() {
             let hex_stream = "8A004A801A8002F478";
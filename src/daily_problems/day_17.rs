@@ -2,32 +2,32 @@ pub mod solutions {
     use itertools::Itertools;
 
     use crate::AocBufReader;
-
-    use lazy_static::lazy_static;
-    use regex::Regex;
-
-    lazy_static! {
-        static ref TARGET_RE: Regex = Regex::new(
-            r"^target area: x=(-?[0-9]*)..(-?[0-9]*), y=(-?[0-9]*)..(-?[0-9]*)$"
-        ).unwrap();
-    }
+    use crate::utils::interval::{AxisBox, InclusiveRange};
+    use crate::utils::parse::scan;
 
 
     #[derive(PartialEq, Eq, Debug)]
     struct Target {
-        min_x: isize,
-        min_y: isize,
-        max_x: isize,
-        max_y: isize
+        area: AxisBox<2>,
     }
 
     impl Target {
         fn new(min_x: isize, min_y: isize, max_x: isize, max_y: isize) -> Target {
-            Target { min_x, min_y, max_x, max_y }
+            Target {
+                area: AxisBox::new([InclusiveRange::new(min_x, max_x), InclusiveRange::new(min_y, max_y)])
+            }
+        }
+
+        fn x(&self) -> InclusiveRange {
+            self.area.ranges[0]
+        }
+
+        fn y(&self) -> InclusiveRange {
+            self.area.ranges[1]
         }
 
         fn contains_probe(&self, probe: &Probe) -> bool {
-            self.min_x <= probe.sx && probe.sx <= self.max_x && self.min_y <= probe.sy && probe.sy <= self.max_y
+            self.area.contains_point(&[probe.sx, probe.sy])
         }
     }
 
@@ -64,7 +64,7 @@ pub mod solutions {
 
         fn hits(&mut self, target: &Target) -> bool {
             loop {
-                if self.sy < target.min_y || self.sx > target.max_x { return false }
+                if self.sy < target.y().min || self.sx > target.x().max { return false }
                 if target.contains_probe(&self) { return true }
                 self.step()
             }
@@ -76,26 +76,22 @@ pub mod solutions {
     }
 
     fn read_input(input: String) -> Target {
-        let cap = TARGET_RE.captures(&input).unwrap();
-        Target::new(
-            cap.get(1).unwrap().as_str().parse::<isize>().unwrap(),
-            cap.get(3).unwrap().as_str().parse::<isize>().unwrap(),
-            cap.get(2).unwrap().as_str().parse::<isize>().unwrap(),
-            cap.get(4).unwrap().as_str().parse::<isize>().unwrap(),
-        )
-
+        let (min_x, max_x, min_y, max_y) = scan::<(isize, isize, isize, isize)>(
+            "target area: x={}..{}, y={}..{}", &input
+        ).unwrap();
+        Target::new(min_x, min_y, max_x, max_y)
     }
 
 
     fn get_min_vx(target: &Target) -> isize {
-        (-0.5 + (0.25 + 2.0 * target.min_x as f64).powf(0.5)).floor() as isize
+        (-0.5 + (0.25 + 2.0 * target.x().min as f64).powf(0.5)).floor() as isize
     }
 
 
     fn _part_1(input: String) -> usize {
         let target = read_input(input);
         let mut max_vy: isize = isize::MIN;
-        for (vx_initial, vy_initial) in (get_min_vx(&target)..target.max_x + 1).cartesian_product(0..target.min_y.abs()) {
+        for (vx_initial, vy_initial) in (get_min_vx(&target)..target.x().max + 1).cartesian_product(0..target.y().min.abs()) {
             if vy_initial > max_vy && Probe::new(vx_initial, vy_initial).hits(&target) {
                 max_vy = vy_initial;
             }
@@ -108,7 +104,7 @@ pub mod solutions {
     fn _part_2(input: String) -> usize {
         let target = read_input(input);
         let mut n_valid_paths: usize = 0;
-        for (vx_initial, vy_initial) in (get_min_vx(&target)..target.max_x + 1).cartesian_product(-target.min_y.abs()..target.min_y.abs()) {
+        for (vx_initial, vy_initial) in (get_min_vx(&target)..target.x().max + 1).cartesian_product(-target.y().min.abs()..target.y().min.abs()) {
             if Probe::new(vx_initial, vy_initial).hits(&target) {
                 n_valid_paths += 1
             }
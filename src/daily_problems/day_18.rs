@@ -1,175 +1,258 @@
 pub mod solutions {
+    use std::fmt;
+    use std::iter::{once, Sum};
+    use std::ops::{Add, AddAssign};
+    use std::str::FromStr;
+
     use itertools::Itertools;
-    use lazy_static::lazy_static;
-    use regex::Regex;
 
     use crate::AocBufReader;
 
-    lazy_static! {
-        static ref ANY_INTERNAL_PAIR: Regex = Regex::new(
-            r"^(.*)\[([0-9]*),([0-9]*)\](.*)$"
-        ).unwrap();
+    const EXPLODE_DEPTH: usize = 5;
+    const SPLIT_THRESHOLD: usize = 10;
+
 
-        static ref LEFT_MOST_INTERNAL_PAIR: Regex = Regex::new(
-            r"^\[([0-9]*),([0-9]*)\](.*)$"
-        ).unwrap();
+    /// A snailfish number stored as its regular numbers from left to right,
+    /// each with the number of pairs enclosing it. Because every pair has
+    /// exactly two children, the depths alone are enough to rebuild the tree.
+    #[derive(PartialEq, Eq, Debug, Clone)]
+    struct SnailFishNumber {
+        leaves: Vec<(usize, usize)>,
+    }
 
-        static ref RIGHT_MOST_NUM: Regex = Regex::new(
-            r"^(.*[^0-9])([0-9]+)([^0-9]*)$"
-        ).unwrap();
 
-        static ref LEFT_MOST_NUM: Regex = Regex::new(
-            r"^([^0-9]*)([0-9]+)([^0-9].*)$"
-        ).unwrap();
+    impl FromStr for SnailFishNumber {
+        type Err = String;
 
-        static ref VAL_GREATER_THAN_TEN:Regex = Regex::new(
-            r"^(.*[^0-9])([0-9]+[0-9]+)([^0-9].*)$"
-        ).unwrap();
+        fn from_str(s: &str) -> Result<SnailFishNumber, String> {
+            let mut leaves: Vec<(usize, usize)> = vec![];
+            let mut depth: usize = 0;
+            let mut digits = String::new();
+            for c in s.chars() {
+                if c.is_ascii_digit() {
+                    digits.push(c);
+                    continue
+                }
+                if !digits.is_empty() {
+                    leaves.push((digits.parse().unwrap(), depth));
+                    digits.clear();
+                }
+                match c {
+                    '[' => depth += 1,
+                    ']' => depth = depth.checked_sub(1).ok_or(format!("unbalanced brackets in {}", s))?,
+                    ',' => (),
+                    _ => return Err(format!("unexpected char {:?} in {}", c, s))
+                }
+            }
+            if !digits.is_empty() {
+                leaves.push((digits.parse().unwrap(), depth));
+            }
 
-        static ref LEFT_MOST_GREATER_THAN_TEN :Regex = Regex::new(
-            r"^([0-9]+[0-9]+)([,\]].*)$"
-        ).unwrap();
+            let number = SnailFishNumber { leaves };
+            match depth == 0 && number.is_well_formed() {
+                true => Ok(number),
+                false => Err(format!("{} is not a snailfish number", s))
+            }
+        }
     }
 
 
-    #[derive(PartialEq, Eq, Debug, Clone)]
-    struct SnailFishNumber {
-        s: String
+    impl fmt::Display for SnailFishNumber {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let mut cursor: usize = 0;
+            self.fmt_subtree(f, &mut cursor, 0)
+        }
     }
 
 
     impl SnailFishNumber {
         fn new(s: String) -> SnailFishNumber {
-            SnailFishNumber { s }
+            s.parse().unwrap()
         }
 
-        fn _get_deep_pair(s: &str) -> Option<Vec<String>> {
-            let mut depth: usize = 0;
-            for (idx, c) in s.chars().enumerate() {
-                if depth >= 4 {
-                    if let Some(capture) = LEFT_MOST_INTERNAL_PAIR.captures(&s[idx..]) {
-                        return Some(vec![
-                            s[..idx].to_string(),
-                            capture.get(1).unwrap().as_str().to_string(),
-                            capture.get(2).unwrap().as_str().to_string(),
-                            capture.get(3).unwrap().as_str().to_string()
-                        ])
-                    }
-                }
-                if c == '[' { depth += 1; }
-                if c == ']' { depth -= 1; }
-            }
-            return None
+        /// The leaf depths must describe a full binary tree: the subtree walk
+        /// has to consume every leaf exactly
+        fn is_well_formed(&self) -> bool {
+            let mut cursor: usize = 0;
+            !self.leaves.is_empty() && self.walk_subtree(&mut cursor, 0) && cursor == self.leaves.len()
         }
 
-        fn _maybe_add_at_right(s: &str, addendum: usize) -> String {
-            if let Some(capture) = LEFT_MOST_NUM.captures(s) {
-                let left_sub_str = capture.get(1).unwrap().as_str();
-                let val = capture.get(2).unwrap().as_str().parse::<usize>().unwrap();
-                let right_sub_str = capture.get(3).unwrap().as_str();
-                return vec![left_sub_str, &(val + addendum).to_string(), right_sub_str].into_iter().collect::<String>()
+        fn walk_subtree(&self, cursor: &mut usize, depth: usize) -> bool {
+            match self.leaves.get(*cursor) {
+                None => false,
+                Some((_, leaf_depth)) if *leaf_depth == depth => {
+                    *cursor += 1;
+                    true
+                },
+                Some((_, leaf_depth)) if *leaf_depth < depth => false,
+                _ => self.walk_subtree(cursor, depth + 1) && self.walk_subtree(cursor, depth + 1)
             }
-            s.to_string()
         }
 
-        fn _maybe_add_left(s: &str, addendum: usize) -> String {
-            if let Some(capture) = RIGHT_MOST_NUM.captures(s) {
-                let left_sub_str = capture.get(1).unwrap().as_str();
-                let val = capture.get(2).unwrap().as_str().parse::<usize>().unwrap();
-                let right_sub_str = capture.get(3).unwrap().as_str();
-                return vec![left_sub_str, &(val + addendum).to_string(), right_sub_str].into_iter().collect::<String>()
+        fn fmt_subtree(&self, f: &mut fmt::Formatter, cursor: &mut usize, depth: usize) -> fmt::Result {
+            let (val, leaf_depth) = self.leaves[*cursor];
+            if leaf_depth == depth {
+                *cursor += 1;
+                return write!(f, "{}", val)
             }
-            s.to_string()
+            write!(f, "[")?;
+            self.fmt_subtree(f, cursor, depth + 1)?;
+            write!(f, ",")?;
+            self.fmt_subtree(f, cursor, depth + 1)?;
+            write!(f, "]")
         }
 
-        fn explode_deeper_than_4(&mut self) {
-            if let Some(str_vec) = SnailFishNumber::_get_deep_pair(&self.s) {
-                let deep_left = str_vec[1].parse::<usize>().unwrap();
-                let deep_right = str_vec[2].parse::<usize>().unwrap();
-
-                self.s = vec![
-                    SnailFishNumber::_maybe_add_left(&str_vec[0], deep_left),
-                    "0".to_string(),
-                    SnailFishNumber::_maybe_add_at_right(&str_vec[3], deep_right)
-                ].into_iter().collect::<String>();
+        fn magnitude_of_subtree(&self, cursor: &mut usize, depth: usize) -> usize {
+            let (val, leaf_depth) = self.leaves[*cursor];
+            if leaf_depth == depth {
+                *cursor += 1;
+                return val
             }
+            3 * self.magnitude_of_subtree(cursor, depth + 1) + 2 * self.magnitude_of_subtree(cursor, depth + 1)
         }
 
-        fn split_greater_than_9(&mut self) {
-            let mut left_sub_str: &str = "";
-            let mut val: usize = 0;
-            let mut right_sub_str: &str = "";
-
-            for (idx, c) in self.s.chars().enumerate() {
-                if let Some(capture) = LEFT_MOST_GREATER_THAN_TEN.captures(&self.s[idx..]) {
-                    left_sub_str = &self.s[..idx];
-                    val = capture.get(1).unwrap().as_str().parse::<usize>().unwrap();
-                    right_sub_str = capture.get(2).unwrap().as_str();
-                    break
-                }
+        /// Explodes the left-most pair nested inside four others; returns whether one was found
+        fn explode_deeper_than_4(&mut self) -> bool {
+            let idx = match self.leaves.iter().position(|(_, depth)| *depth >= EXPLODE_DEPTH) {
+                Some(idx) => idx,
+                None => return false
+            };
+            let (left, depth) = self.leaves[idx];
+            let (right, _) = self.leaves[idx + 1];
+            if idx > 0 {
+                self.leaves[idx - 1].0 += left;
+            }
+            if idx + 2 < self.leaves.len() {
+                self.leaves[idx + 2].0 += right;
             }
+            self.leaves.splice(idx..idx + 2, vec![(0, depth - 1)]);
+            true
+        }
 
-            self.s = vec![
-                left_sub_str.to_string(),
-                "[".to_string(),
-                (val / 2).to_string(),
-                ",".to_string(),
-                (val - (val / 2)).to_string(),
-                "]".to_string(),
-                right_sub_str.to_string()
-            ].into_iter().collect::<String>();
+        /// Splits the left-most regular number of 10 or more; returns whether one was found
+        fn split_greater_than_9(&mut self) -> bool {
+            let idx = match self.leaves.iter().position(|(val, _)| *val >= SPLIT_THRESHOLD) {
+                Some(idx) => idx,
+                None => return false
+            };
+            let (val, depth) = self.leaves[idx];
+            self.leaves.splice(idx..idx + 1, vec![(val / 2, depth + 1), (val - val / 2, depth + 1)]);
+            true
         }
 
         fn reduce(&mut self) {
-            loop {
-                if let Some(_) = SnailFishNumber::_get_deep_pair(&self.s) {
-                    self.explode_deeper_than_4();
-                } else if let Some(_) = VAL_GREATER_THAN_TEN.captures(&self.s) {
-                    self.split_greater_than_9();
-                } else {
-                    break
-                }
+            while self.explode_deeper_than_4() || self.split_greater_than_9() {}
+        }
+
+        /// Makes self the pair [self, other] without reducing the result
+        fn pair_with(&mut self, other: SnailFishNumber) {
+            self.leaves.extend(other.leaves);
+            for leaf in self.leaves.iter_mut() {
+                leaf.1 += 1;
             }
         }
 
-        fn add(&mut self, other: SnailFishNumber) {
-            let new_str = vec![
-                "[".to_string(),
-                self.s.clone(),
-                ",".to_string(),
-                other.s,
-                "]".to_string()
-            ].into_iter().collect::<String>();
+        /// Yields the number after every explode or split on the way to being reduced
+        fn reduction_steps(self) -> Reduction {
+            Reduction { number: self }
+        }
 
-            self.s = new_str;
-            self.reduce()
+        /// Adds other to self, yielding the unreduced sum followed by every reduction step
+        fn traced_add(mut self, other: SnailFishNumber) -> impl Iterator<Item = (ReductionStep, SnailFishNumber)> {
+            self.pair_with(other);
+            let sum = self;
+            once((ReductionStep::Addition, sum.clone())).chain(sum.reduction_steps())
         }
 
         fn magnitude(&self) -> usize {
-            let mut result: String = self.s.clone();
-            while let Some(capture) = ANY_INTERNAL_PAIR.captures(&result) {
-                let left_sub_str = capture.get(1).unwrap().as_str();
-                let left_val = capture.get(2).unwrap().as_str().parse::<usize>().unwrap();
-                let right_val = capture.get(3).unwrap().as_str().parse::<usize>().unwrap();
-                let right_sub_str = capture.get(4).unwrap().as_str();
-                result = vec![
-                    left_sub_str.to_string(),
-                    (3 * left_val + 2 * right_val).to_string(),
-                    right_sub_str.to_string()
-                ].into_iter().collect()
+            let mut cursor: usize = 0;
+            self.magnitude_of_subtree(&mut cursor, 0)
+        }
+    }
+
+
+    impl Add for SnailFishNumber {
+        type Output = SnailFishNumber;
+
+        fn add(mut self, other: SnailFishNumber) -> SnailFishNumber {
+            self += other;
+            self
+        }
+    }
+
+
+    impl AddAssign for SnailFishNumber {
+        fn add_assign(&mut self, other: SnailFishNumber) {
+            self.pair_with(other);
+            self.reduce();
+        }
+    }
 
+
+    /// Snailfish addition has no zero, so summing an empty iterator panics
+    impl Sum for SnailFishNumber {
+        fn sum<I: Iterator<Item = SnailFishNumber>>(mut iter: I) -> SnailFishNumber {
+            let first = iter.next().expect("cannot sum zero snailfish numbers");
+            iter.fold(first, |total, number| total + number)
+        }
+    }
+
+
+    /// What happened to a snailfish number on the way from one trace line to the next
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum ReductionStep {
+        Addition,
+        Explode,
+        Split,
+    }
+
+
+    impl fmt::Display for ReductionStep {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                ReductionStep::Addition => write!(f, "after addition:"),
+                ReductionStep::Explode => write!(f, "after explode: "),
+                ReductionStep::Split => write!(f, "after split:   "),
             }
-            result.parse::<usize>().unwrap()
         }
     }
 
 
-    pub fn part_1(mut aoc_reader: AocBufReader) -> usize {
-       let mut sum = SnailFishNumber::new(aoc_reader.next().unwrap());
-       while let Some(line) = aoc_reader.next() {
-           sum.add(SnailFishNumber::new(line));
-       }
+    struct Reduction {
+        number: SnailFishNumber,
+    }
+
+
+    impl Iterator for Reduction {
+        type Item = (ReductionStep, SnailFishNumber);
+
+        fn next(&mut self) -> Option<Self::Item> {
+            if self.number.explode_deeper_than_4() {
+                Some((ReductionStep::Explode, self.number.clone()))
+            } else if self.number.split_greater_than_9() {
+                Some((ReductionStep::Split, self.number.clone()))
+            } else {
+                None
+            }
+        }
+    }
+
+
+    pub fn part_1(aoc_reader: AocBufReader) -> usize {
+        let sum: SnailFishNumber = aoc_reader.map(SnailFishNumber::new).sum();
+        sum.magnitude()
+    }
+
 
+    /// Part 1, printing every addition, explode and split in the puzzle's walkthrough format
+    pub fn trace_part_1(mut aoc_reader: AocBufReader) -> usize {
+        let mut sum = SnailFishNumber::new(aoc_reader.next().unwrap());
+        for line in aoc_reader {
+            for (step, number) in sum.clone().traced_add(SnailFishNumber::new(line)) {
+                println!("{} {}", step, number);
+                sum = number;
+            }
+        }
         sum.magnitude()
     }
 
@@ -182,11 +265,10 @@ pub mod solutions {
             if idx_1 == idx_2 { continue }
 
 
-            let mut x: SnailFishNumber = snail_fish_numbers[idx_1].clone();
+            let x: SnailFishNumber = snail_fish_numbers[idx_1].clone();
             let y: SnailFishNumber = snail_fish_numbers[idx_2].clone();
 
-            x.add(y);
-            let magnitude = x.magnitude();
+            let magnitude = (x + y).magnitude();
             if magnitude > max_sum {
                 max_sum = magnitude;
             }
@@ -204,27 +286,89 @@ pub mod solutions {
         fn test_explode_deeper_than_4() {
             let mut s = SnailFishNumber::new("[7,[6,[5,[4,[3,2]]]]]".to_string());
             s.explode_deeper_than_4();
-            assert_eq!(s.s, "[7,[6,[5,[7,0]]]]".to_string());
+            assert_eq!(s.to_string(), "[7,[6,[5,[7,0]]]]".to_string());
         }
 
         #[test]
         fn test_split_greater_than_9() {
             let mut s = SnailFishNumber::new("[[[[0,7],4],[[7,8],[0,13]]],[1,1]]".to_string());
             s.split_greater_than_9();
-            assert_eq!(s.s, "[[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]".to_string());
+            assert_eq!(s.to_string(), "[[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]".to_string());
         }
 
         #[test]
         fn test_reduce() {
             let mut s = SnailFishNumber::new("[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]".to_string());
             s.reduce();
-            assert_eq!(s.s, "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]".to_string())
+            assert_eq!(s.to_string(), "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]".to_string())
+        }
+
+        #[test]
+        fn test_round_trip() {
+            for s in [
+                "[1,2]",
+                "[[1,2],3]",
+                "[9,[8,7]]",
+                "[[1,9],[8,5]]",
+                "[[[[1,2],[3,4]],[[5,6],[7,8]]],9]",
+                "[[[9,[3,8]],[[0,9],6]],[[[3,7],[4,9]],3]]",
+                "[[[[1,3],[5,3]],[[1,3],[8,7]]],[[[4,9],[6,9]],[[8,2],[7,3]]]]",
+                "[[[[0,7],4],[[7,8],[0,13]]],[1,1]]",
+            ] {
+                assert_eq!(s.parse::<SnailFishNumber>().unwrap().to_string(), s);
+            }
+        }
+
+        #[test]
+        fn test_malformed() {
+            assert!("[1,2".parse::<SnailFishNumber>().is_err());
+            assert!("[1,2]]".parse::<SnailFishNumber>().is_err());
+            assert!("[1,[2,3,4]]".parse::<SnailFishNumber>().is_err());
+            assert!("[1,x]".parse::<SnailFishNumber>().is_err());
+            assert!("".parse::<SnailFishNumber>().is_err());
+        }
+
+        #[test]
+        fn test_add() {
+            let mut s = SnailFishNumber::new("[[[[4,3],4],4],[7,[[8,4],9]]]".to_string());
+            s += SnailFishNumber::new("[1,1]".to_string());
+            assert_eq!(s.to_string(), "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
+
+            assert_eq!(
+                SnailFishNumber::new("[1,2]".to_string()) + SnailFishNumber::new("[[3,4],5]".to_string()),
+                SnailFishNumber::new("[[1,2],[[3,4],5]]".to_string())
+            );
+        }
+
+        #[test]
+        fn test_sum() {
+            let sum: SnailFishNumber = vec!["[1,1]", "[2,2]", "[3,3]", "[4,4]", "[5,5]", "[6,6]"].into_iter()
+                .map(|s| SnailFishNumber::new(s.to_string()))
+                .sum();
+            assert_eq!(sum.to_string(), "[[[[5,0],[7,4]],[5,5]],[6,6]]");
+        }
+
+        #[test]
+        fn test_traced_add() {
+            let a = SnailFishNumber::new("[[[[4,3],4],4],[7,[[8,4],9]]]".to_string());
+            let b = SnailFishNumber::new("[1,1]".to_string());
+            let trace: Vec<String> = a.traced_add(b).map(|(step, number)| format!("{} {}", step, number)).collect();
+            assert_eq!(trace, vec![
+                "after addition: [[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]",
+                "after explode:  [[[[0,7],4],[7,[[8,4],9]]],[1,1]]",
+                "after explode:  [[[[0,7],4],[15,[0,13]]],[1,1]]",
+                "after split:    [[[[0,7],4],[[7,8],[0,13]]],[1,1]]",
+                "after split:    [[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]",
+                "after explode:  [[[[0,7],4],[[7,8],[6,0]]],[8,1]]",
+            ]);
         }
 
         #[test]
         fn test_magnitude() {
             let s = SnailFishNumber::new("[[1,2],[[3,4],5]]".to_string());
             assert_eq!(s.magnitude(), 143);
+            let s = SnailFishNumber::new("[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]".to_string());
+            assert_eq!(s.magnitude(), 3488);
         }
     }
 }
\ No newline at end of file
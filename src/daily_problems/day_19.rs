@@ -1,16 +1,8 @@
 pub mod solutions {
     use std::collections::{HashMap, HashSet};
 
-    use lazy_static::lazy_static;
-    use regex::Regex;
-
     use crate::AocBufReader;
-
-    lazy_static! {
-        static ref SCANNER_HEADER: Regex = Regex::new(
-            r"^--- scanner ([0-9]+) ---$"
-        ).unwrap();
-    }
+    use crate::utils::parse::{scan, signed_ints};
 
 
     enum Rotation {
@@ -118,15 +110,14 @@ pub mod solutions {
     impl Scanner {
         fn from_aoc_iter_mut(aoc_reader: &mut AocBufReader) -> Option<Scanner> {
             if let Some(header) = aoc_reader.next() {
-                let scanner_id: usize = SCANNER_HEADER.captures(&header).unwrap().get(1)
-                    .unwrap().as_str().parse().unwrap();
+                let (scanner_id,) = scan::<(usize,)>("--- scanner {} ---", &header).unwrap();
                 let mut probes: HashSet<Point> = HashSet::new();
                 loop {
                     let line = aoc_reader.next();
                     if line == None || line.as_ref().unwrap().len() == 0 {
                         break
                     }
-                    let vals: Vec<isize> = line.unwrap().split(",").map(|s| s.parse().unwrap()).collect();
+                    let vals: Vec<isize> = signed_ints(&line.unwrap()).unwrap();
                     probes.insert(Point::new(vals[0], vals[1], vals[2]));
                 }
 
@@ -1,187 +1,165 @@
 pub mod solutions {
-    use std::collections::HashSet;
-    use std::hash::Hash;
-
-    use itertools::Itertools;
-
     use crate::AocBufReader;
+    use crate::utils::automaton::{Automaton, Simulation};
     use crate::utils::conversion::binary_bool_vec_to_usize;
+    use crate::utils::grid::Grid;
+    use crate::utils::netpbm::write_pbm;
 
-
-    #[derive(Debug, PartialEq, Eq, Hash)]
-    struct Point {
-        row: isize,
-        column: isize,
-    }
-
-
-    impl Point {
-        fn new(row: isize, column: isize) -> Point {
-            Point { row, column }
-        }
-
-        fn self_and_neighbors(&self) -> Vec<Point> {
-            (-1isize..=1).cartesian_product(-1isize..=1).map(|(drow, dcol)| {
-                Point::new(self.row + drow, self.column + dcol)
-            }).collect::<Vec<Point>>()
-        }
-    }
+    const IMAGE_PATH: &str = "day_20_pt_2.pbm";
+    const IMAGE_SCALE: usize = 4;
 
 
+    /// A finite window of pixels on an infinite image, with every pixel
+    /// outside the window set to background. Each step grows the window by
+    /// one pixel on every side, as those are the only pixels outside it whose
+    /// neighbourhoods see anything but background.
     struct Image {
         enhancement_algorithm: Vec<bool>,
-        light_pixels: Option<HashSet<Point>>,
-        dark_pixels: Option<HashSet<Point>>,
-        flicker: bool,
+        pixels: Grid<bool>,
+        background: bool,
     }
 
 
     impl Image {
-        fn new(enhancement_algorithm: Vec<bool>, light_pixels: HashSet<Point>, flicker: bool) -> Image {
-            Image { enhancement_algorithm, light_pixels: Some(light_pixels), dark_pixels: None, flicker }
+        fn new(enhancement_algorithm: Vec<bool>, pixels: Grid<bool>) -> Image {
+            Image { enhancement_algorithm, pixels, background: false }
         }
 
-        fn light_pixels_and_neighbors(&self) -> HashSet<Point> {
-            let mut light_pixels_and_neighbors: HashSet<Point> = HashSet::new();
-            for pixel in self.light_pixels.as_ref().unwrap().iter() {
-                light_pixels_and_neighbors.extend(pixel.self_and_neighbors())
+        /// The pixel at (row, col) in window coordinates, which may lie outside the window
+        fn pixel(&self, row: isize, col: isize) -> bool {
+            if row < 0 || col < 0 || row as usize >= self.pixels.n_rows() || col as usize >= self.pixels.n_cols() {
+                return self.background
             }
-            light_pixels_and_neighbors
+            *self.pixels.get(row as usize, col as usize)
         }
 
-        fn dark_pixels_and_neighbors(&self) -> HashSet<Point> {
-            let mut dark_pixels_and_neighbors: HashSet<Point> = HashSet::new();
-            for pixel in self.dark_pixels.as_ref().unwrap().iter() {
-                dark_pixels_and_neighbors.extend(pixel.self_and_neighbors())
-            }
-            dark_pixels_and_neighbors
+        /// The 3x3 neighbourhood of (row, col) read top left to bottom right as a binary number
+        fn get_algorithm_key(&self, row: isize, col: isize) -> usize {
+            binary_bool_vec_to_usize(
+                (-1isize..=1).flat_map(|drow| (-1isize..=1).map(move |dcol| (drow, dcol)))
+                    .map(|(drow, dcol)| self.pixel(row + drow, col + dcol))
+                    .collect()
+            )
         }
 
-        fn get_algorithm_key(&self, pixel: &Point) -> usize {
-            if let Some(light_pixels) = self.light_pixels.as_ref() {
-                return binary_bool_vec_to_usize(
-                    pixel.self_and_neighbors().into_iter().map(|p| {
-                        light_pixels.contains(&p)
-                    }).collect::<Vec<bool>>()
-                )
+        fn enhance_once(&mut self) {
+            let mut pixels = Grid::new(self.pixels.n_rows() + 2, self.pixels.n_cols() + 2, false);
+            for (row, col) in pixels.coordinates() {
+                let key = self.get_algorithm_key(row as isize - 1, col as isize - 1);
+                pixels.set(row, col, self.enhancement_algorithm[key]);
             }
-            if let Some(dark_pixels) = self.dark_pixels.as_ref() {
-                return binary_bool_vec_to_usize(
-                    pixel.self_and_neighbors().into_iter().map(|p| {
-                        !dark_pixels.contains(&p)
-                    }).collect::<Vec<bool>>()
-                )
+            // a background pixel's neighbourhood is all background, so key 0 or 511
+            let background_key = if self.background { self.enhancement_algorithm.len() - 1 } else { 0 };
+            self.background = self.enhancement_algorithm[background_key];
+            self.pixels = pixels;
+        }
+
+        /// How many pixels are lit, or None when the lit background makes it infinite
+        fn lit_count(&self) -> Option<usize> {
+            match self.background {
+                true => None,
+                false => Some(self.pixels.rows().iter().flatten().filter(|lit| **lit).count())
             }
+        }
 
-            panic!("AHHHHHH!");
+        /// Steps n_steps times, returning the lit count after each step
+        fn enhance(&mut self, n_steps: usize) -> Vec<Option<usize>> {
+            let mut lit_counts: Vec<Option<usize>> = vec![];
+            Simulation::new(self).observe(|_, image| lit_counts.push(image.lit_count())).run(n_steps);
+            lit_counts
         }
 
-        fn step(&mut self) {
-            if self.flicker {
-                // This is in the scenario where the whole universe flickers every other step
-                if self.light_pixels != None {
-                    // we are currently tracking light pixels, but won't be able to next round
-                    // there will be infinitely many.
-                    let mut new_dark_pixels: HashSet<Point> = HashSet::new();
-                    for pixel in self.light_pixels_and_neighbors() {
-                        if !self.enhancement_algorithm[self.get_algorithm_key(&pixel)] {
-                            new_dark_pixels.insert(pixel);
-                        }
-                    }
-                    self.dark_pixels = Some(new_dark_pixels);
-                    self.light_pixels = None;
-                } else {
-                    let mut new_light_pixels: HashSet<Point> = HashSet::new();
-                    for pixel in self.dark_pixels_and_neighbors() {
-                        if self.enhancement_algorithm[self.get_algorithm_key(&pixel)] {
-                            new_light_pixels.insert(pixel);
-                        }
-                    }
-                    self.light_pixels = Some(new_light_pixels);
-                    self.dark_pixels = None;
-                }
-            } else {
-                // We are in the more stable case where there are always finitely many light pixels.
-                let mut new_light_pixels: HashSet<Point> = HashSet::new();
-                for pixel in self.light_pixels_and_neighbors() {
-                    if self.enhancement_algorithm[self.get_algorithm_key(&pixel)] {
-                        new_light_pixels.insert(pixel);
-                    }
-                }
-                self.light_pixels = Some(new_light_pixels)
+        /// The window with a margin of background pixels on every side, so
+        /// the background shows whichever phase the image is in
+        fn to_grid(&self, margin: usize) -> Grid<bool> {
+            let mut grid = Grid::new(self.pixels.n_rows() + 2 * margin, self.pixels.n_cols() + 2 * margin, false);
+            for (row, col) in grid.coordinates() {
+                grid.set(row, col, self.pixel(row as isize - margin as isize, col as isize - margin as isize));
             }
+            grid
+        }
+
+        fn render(&self, margin: usize) -> String {
+            self.to_grid(margin).render(|lit| if *lit { '#' } else { '.' })
         }
 
         fn print(&self) {
-            let min_row: isize = self.light_pixels.as_ref().unwrap().iter().map(|p| p.row).min().unwrap();
-            let min_col: isize = self.light_pixels.as_ref().unwrap().iter().map(|p| p.column).min().unwrap();
-            let max_row: isize = self.light_pixels.as_ref().unwrap().iter().map(|p| p.row).max().unwrap();
-            let max_col: isize = self.light_pixels.as_ref().unwrap().iter().map(|p| p.column).max().unwrap();
-
-            for row in min_row..=max_row {
-                println!(
-                    "{}",
-                    (min_col..=max_col).map(|col| {
-                        match self.light_pixels.as_ref().unwrap().contains(&Point::new(row, col)) {
-                            true => '#',
-                            false => '.'
-                        }
-                    }).collect::<String>()
-                );
-            }
+            println!("{}", self.render(1));
         }
     }
 
 
-    fn read_input(mut aoc_reader: AocBufReader) -> Image {
-        let algorithm_line = aoc_reader.next().unwrap();
-
-        let mut enhancement_algorithm: Vec<bool> = vec![];
-        for c in algorithm_line.chars() {
-            match c {
-                '.' => enhancement_algorithm.push(false),
-                '#' => enhancement_algorithm.push(true),
-                _ => panic!("Unknown character!")
-            }
+    impl Automaton for Image {
+        fn step(&mut self) {
+            self.enhance_once()
         }
+    }
 
-        aoc_reader.next();  // blank line
-        let mut light_pixels: HashSet<Point> = HashSet::new();
-        let mut row: isize = 0;
-        while let Some(line) = aoc_reader.next() {
-            for (column, c) in line.chars().enumerate() {
-                match c {
-                    '.' => (),
-                    '#' => { light_pixels.insert(Point::new(row, column as isize)); },
-                    _ => panic!("Unknown character!")
-                }
-            }
-            row += 1;
+
+    fn parse_pixel(c: char) -> bool {
+        match c {
+            '.' => false,
+            '#' => true,
+            _ => panic!("Unknown character!")
         }
+    }
+
+
+    fn read_lines(mut lines: impl Iterator<Item = String>) -> Image {
+        let enhancement_algorithm: Vec<bool> = lines.next().unwrap().chars().map(parse_pixel).collect();
+        lines.next();  // blank line
+        let pixels = Grid::from_lines(lines, parse_pixel);
 
         // If the first character of the enhancement algorithm is `#`,
         // All pixels (infinitely many in the abyss) will turn on
         // during the first step. For the solution to be finite (on even steps),
         // the last character _must_ be `.`
-        let flicker: bool = enhancement_algorithm[0];
-        Image::new(enhancement_algorithm, light_pixels, flicker)
+        Image::new(enhancement_algorithm, pixels)
+    }
+
+
+    fn read_input(aoc_reader: AocBufReader) -> Image {
+        read_lines(aoc_reader)
+    }
+
+
+    fn lit_after(mut image: Image, n_steps: usize) -> usize {
+        let lit_counts = image.enhance(n_steps);
+        lit_counts.last().copied().flatten().expect("infinitely many pixels are lit")
     }
 
 
     pub fn part_1(aoc_reader: AocBufReader) -> usize {
-        let mut image = read_input(aoc_reader);
-        image.step();
-        image.step();
-        image.light_pixels.as_ref().unwrap().len()
+        lit_after(read_input(aoc_reader), 2)
     }
 
 
     pub fn part_2(aoc_reader: AocBufReader) -> usize {
+        lit_after(read_input(aoc_reader), 50)
+    }
+
+
+    /// Part 2, printing the lit count after every step and the final image
+    pub fn trace_part_2(aoc_reader: AocBufReader) -> usize {
         let mut image = read_input(aoc_reader);
-        for _ in 0..50 {
-            image.step();
+        for (step_idx, lit_count) in image.enhance(50).iter().enumerate() {
+            match lit_count {
+                Some(count) => println!("step {}: {} lit", step_idx + 1, count),
+                None => println!("step {}: infinitely many lit", step_idx + 1),
+            }
         }
-        image.light_pixels.as_ref().unwrap().len()
+        image.print();
+        image.lit_count().unwrap()
+    }
+
+
+    /// Part 2, also saving the final image as a PBM
+    pub fn export_part_2(aoc_reader: AocBufReader) -> usize {
+        let mut image = read_input(aoc_reader);
+        image.enhance(50);
+        write_pbm(IMAGE_PATH, &image.to_grid(1), IMAGE_SCALE)
+            .unwrap_or_else(|err| panic!("could not write {}: {}", IMAGE_PATH, err));
+        image.lit_count().unwrap()
     }
 
 
@@ -189,16 +167,60 @@ pub mod solutions {
     mod tests {
         use super::*;
 
+        const EXAMPLE_ALGORITHM: [&str; 6] = [
+            "..#.#..#####.#.#.#.###.##.....###.##.#..###.####..#####..#....#..#..##..###..######.###...####..",
+            "#..#####..##..#.#####...##.#.#..#.##..#.#......#.###.######.###.####...#.##.##..#..#..#####.....",
+            "#.#....###..#.##......#.....#..#..#..##..#...##.######.####.####.#.#...#.......#..#.#.#...####.#",
+            "#.#......#..#...##.#.##..#...##.#.##..###.#......#.#.......#.#.#.####.###.##...#.....####.#..#..",
+            "#.##.#....##..#.####....##...##..#...#......#.#.......#.......##..####..#...#.#.#...##..#.#..###",
+            "..#####........#..####......#..#",
+        ];
+
+        fn example() -> Image {
+            let lines = vec![EXAMPLE_ALGORITHM.concat(), String::new()].into_iter()
+                .chain(["#..#.", "#....", "##..#", "..#..", "..###"].iter().map(|line| line.to_string()));
+            read_lines(lines)
+        }
+
+        /// The example's image with the algorithm's first and last entries
+        /// swapped, so the background lights up on odd steps
+        fn flickering_example() -> Image {
+            let mut image = example();
+            let n_entries = image.enhancement_algorithm.len();
+            image.enhancement_algorithm[0] = true;
+            image.enhancement_algorithm[n_entries - 1] = false;
+            image
+        }
+
+        #[test]
+        fn test_algorithm_key() {
+            let image = example();
+            assert_eq!(image.get_algorithm_key(2, 2), 34);
+            assert_eq!(image.get_algorithm_key(-5, -5), 0);
+            assert_eq!(image.get_algorithm_key(-1, -1), 1);
+        }
+
+        #[test]
+        fn test_enhance() {
+            let mut image = example();
+            assert_eq!(image.lit_count(), Some(10));
+            assert_eq!(image.enhance(2), vec![Some(24), Some(35)]);
+            assert_eq!(image.pixels.n_rows(), 9);
+            assert_eq!(image.enhance(48).last(), Some(&Some(3351)));
+        }
+
         #[test]
-        fn test_self_and_neighbors() {
-            assert_eq!(
-                Point::new(1, 1).self_and_neighbors(),
-                vec![
-                    Point::new(0, 0), Point::new(0, 1), Point::new(0, 2),
-                    Point::new(1, 0), Point::new(1, 1), Point::new(1, 2),
-                    Point::new(2, 0), Point::new(2, 1), Point::new(2, 2),
-                ]
-            );
+        fn test_flickering_background() {
+            let mut image = flickering_example();
+            let lit_counts = image.enhance(4);
+            assert_eq!(lit_counts[0], None);
+            assert_eq!(lit_counts[2], None);
+            assert!(lit_counts[1].is_some() && lit_counts[3].is_some());
+
+            image.enhance_once();
+            let rendered = image.render(2);
+            assert!(rendered.lines().next().unwrap().chars().all(|c| c == '#'));
+            assert_eq!(rendered.lines().count(), image.pixels.n_rows() + 4);
         }
     }
-}
\ No newline at end of file
+}
@@ -3,15 +3,11 @@ pub mod solutions {
     use std::collections::HashMap;
 
     use lazy_static::lazy_static;
-    use regex::Regex;
 
     use crate::AocBufReader;
+    use crate::utils::parse::scan;
 
     lazy_static! {
-        static ref INPUT_RE: Regex = Regex::new(
-            r"^Player ([0-9]*) starting position: ([0-9]*)$"
-        ).unwrap();
-
         static ref DIRAC_DIE_THREE_ROLL_SUMS: Vec<usize> = vec![
             (1 + 1 + 1),  // all rolls with three 1s
             (1 + 1 + 2),  // all rolls with two 1s
@@ -118,13 +114,8 @@ pub mod solutions {
         fn from_reader(aoc_reader: AocBufReader) -> Vec<Player> {
             let mut players: Vec<Player> = Vec::new();
             for line in aoc_reader {
-                let capture = INPUT_RE.captures(&line).unwrap();
-                players.push(
-                    Player::new(
-                        capture.get(1).unwrap().as_str().parse::<usize>().unwrap(),
-                        capture.get(2).unwrap().as_str().parse::<usize>().unwrap()
-                    )
-                );
+                let (id, position) = scan::<(usize, usize)>("Player {} starting position: {}", &line).unwrap();
+                players.push(Player::new(id, position));
             }
             players
         }
@@ -1,38 +1,27 @@
 pub mod solutions {
-    use std::cmp::{max, min};
-
-    use lazy_static::lazy_static;
-    use regex::Regex;
+    use std::collections::HashMap;
 
     use crate::AocBufReader;
+    use crate::utils::interval::{AxisBox, InclusiveRange};
+    use crate::utils::parse::scan;
 
-    lazy_static! {
-        static ref INPUT_RE: Regex = Regex::new(
-            r"^([fon]*) x=(-?[0-9]*)\.\.(-?[0-9]*),y=(-?[0-9]*)\.\.(-?[0-9]*),z=(-?[0-9]*)\.\.(-?[0-9]*)"
-        ).unwrap();
-    }
+    const INITIALIZATION_REGION_LIMIT: isize = 50;
 
 
+    /// Strategy used to count the cubes left on after a reboot
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-    struct InclusiveRange {
-        min: isize,
-        max: isize,
-    }
-
-
-    impl InclusiveRange {
-        fn new(min: isize, max: isize) -> InclusiveRange {
-            InclusiveRange { min, max }
-        }
+    pub enum RebootEngine {
+        /// Recursive inclusion-exclusion over every later cube; exponential in overlap depth
+        InclusionExclusion,
+        /// Counts per distinct cuboid; each step cancels its overlap with every entry so far
+        SignedCuboids,
     }
 
 
     #[derive(Debug, PartialEq, Eq)]
     struct Cube {
         on: bool,
-        x: InclusiveRange,
-        y: InclusiveRange,
-        z: InclusiveRange,
+        region: AxisBox<3>,
     }
 
 
@@ -45,66 +34,49 @@ pub mod solutions {
         ) -> Cube {
             Cube {
                 on: on,
-                x: InclusiveRange::new(min_x, max_x),
-                y: InclusiveRange::new(min_y, max_y),
-                z: InclusiveRange::new(min_z, max_z),
+                region: AxisBox::new([
+                    InclusiveRange::new(min_x, max_x),
+                    InclusiveRange::new(min_y, max_y),
+                    InclusiveRange::new(min_z, max_z),
+                ]),
             }
         }
 
         fn from_string(s: String) -> Cube {
-            let capture = INPUT_RE.captures(&s).unwrap();
-            let on_off = capture.get(1).unwrap().as_str();
+            let (on_off, min_x, max_x, min_y, max_y, min_z, max_z) = scan::<(String, isize, isize, isize, isize, isize, isize)>(
+                "{} x={}..{},y={}..{},z={}..{}", &s
+            ).unwrap();
             let cube_is_on: bool;
-            match on_off {
+            match on_off.as_str() {
                 "on" => cube_is_on = true,
                 "off" => cube_is_on = false,
                 _ => panic!("UP IS DOWN AHHH")
             }
 
-            Cube::new(
-                cube_is_on,
-                capture.get(2).unwrap().as_str().parse::<isize>().unwrap(),
-                capture.get(3).unwrap().as_str().parse::<isize>().unwrap(),
-                capture.get(4).unwrap().as_str().parse::<isize>().unwrap(),
-                capture.get(5).unwrap().as_str().parse::<isize>().unwrap(),
-                capture.get(6).unwrap().as_str().parse::<isize>().unwrap(),
-                capture.get(7).unwrap().as_str().parse::<isize>().unwrap(),
-            )
+            Cube::new(cube_is_on, min_x, max_x, min_y, max_y, min_z, max_z)
         }
 
-        fn is_disjoint(&self, other: &Cube) -> bool {
-            other.x.min > self.x.max || other.y.min > self.y.max || other.z.min > self.z.max ||
-            other.x.max < self.x.min || other.y.max < self.y.min || other.z.max < self.z.min
+        fn intersection(&self, other: &Cube, on: bool) -> Option<Cube> {
+            self.region.intersection(&other.region).map(|region| Cube { on, region })
         }
 
-        fn intersection(&self, other: &Cube, on: bool) -> Option<Cube> {
-            if self.is_disjoint(other) {
-                None
-            } else {
-                let cube = Cube::new(
-                    on,
-                    max(self.x.min, other.x.min),
-                    min(self.x.max, other.x.max),
-                    max(self.y.min, other.y.min),
-                    min(self.y.max, other.y.max),
-                    max(self.z.min, other.z.min),
-                    min(self.z.max, other.z.max),
-                );
-                Some(cube)
-            }
+        /// The part of self within bounds, keeping self's on/off state
+        fn clip(&self, bounds: &AxisBox<3>) -> Option<Cube> {
+            self.region.clip(bounds).map(|region| Cube { on: self.on, region })
         }
 
         fn print(&self) {
+            let [x, y, z] = self.region.ranges;
             println!(
                 "x:{}-{}, y:{}-{}, z:{}-{}",
-                self.x.min, self.x.max,
-                self.y.min, self.y.max,
-                self.z.min, self.z.max
+                x.min, x.max,
+                y.min, y.max,
+                z.min, z.max
             );
         }
 
         fn boundary_volume(&self) -> isize {
-            (self.x.max - self.x.min + 1) * (self.y.max - self.y.min + 1) * (self.z.max - self.z.min + 1)
+            self.region.volume() as isize
         }
     }
 
@@ -131,23 +103,102 @@ pub mod solutions {
     }
 
 
-    pub fn part_1(aoc_reader: AocBufReader) -> usize {
-        // test in bound values: 590784
-        let cubes = read_input(aoc_reader);
+    fn initialization_region() -> AxisBox<3> {
+        AxisBox::new([InclusiveRange::new(-INITIALIZATION_REGION_LIMIT, INITIALIZATION_REGION_LIMIT); 3])
+    }
 
+
+    fn reboot_by_inclusion_exclusion(cubes: &[Cube]) -> usize {
         let mut total_on: usize = 0;
         for idx in 0usize..cubes.len() {
             total_on += final_contribution_from_this_cube(&cubes[idx], &cubes[idx + 1..])
         }
-
         total_on
     }
 
 
+    /// Keeps a count for each region such that the counted volumes sum to the
+    /// lit count. Each new cube first subtracts the count of every region it
+    /// overlaps from that overlap, zeroing it, then adds itself once if it is on.
+    /// Equal regions share one entry and entries that reach zero are dropped, so
+    /// nested cubes don't multiply the entries the way they multiply terms in
+    /// inclusion-exclusion.
+    fn reboot_by_signed_cuboids(cubes: &[Cube]) -> usize {
+        let mut signed: HashMap<AxisBox<3>, isize> = HashMap::new();
+        for cube in cubes {
+            let mut updates: HashMap<AxisBox<3>, isize> = HashMap::new();
+            for (region, count) in signed.iter() {
+                if let Some(overlap) = region.intersection(&cube.region) {
+                    *updates.entry(overlap).or_insert(0) -= count;
+                }
+            }
+            if cube.on {
+                *updates.entry(cube.region).or_insert(0) += 1;
+            }
+            for (region, change) in updates {
+                let count = signed.entry(region).or_insert(0);
+                *count += change;
+                if *count == 0 {
+                    signed.remove(&region);
+                }
+            }
+        }
+        signed.iter().map(|(region, count)| region.volume() as isize * count).sum::<isize>() as usize
+    }
+
+
+    /// Number of cubes left on after applying every reboot step in order
+    fn reboot(cubes: &[Cube], engine: RebootEngine) -> usize {
+        match engine {
+            RebootEngine::InclusionExclusion => reboot_by_inclusion_exclusion(cubes),
+            RebootEngine::SignedCuboids => reboot_by_signed_cuboids(cubes),
+        }
+    }
+
+
+    fn _part_1(cubes: Vec<Cube>, engine: RebootEngine) -> usize {
+        let bounds = initialization_region();
+        let clipped: Vec<Cube> = cubes.iter().filter_map(|cube| cube.clip(&bounds)).collect();
+        reboot(&clipped, engine)
+    }
+
+
+    pub fn part_1_with_engine(aoc_reader: AocBufReader, engine: RebootEngine) -> usize {
+        _part_1(read_input(aoc_reader), engine)
+    }
+
+
+    pub fn part_1(aoc_reader: AocBufReader) -> usize {
+        part_1_with_engine(aoc_reader, RebootEngine::SignedCuboids)
+    }
+
+
+    fn _part_2(cubes: Vec<Cube>, engine: RebootEngine) -> usize {
+        reboot(&cubes, engine)
+    }
+
+
+    pub fn part_2_with_engine(aoc_reader: AocBufReader, engine: RebootEngine) -> usize {
+        _part_2(read_input(aoc_reader), engine)
+    }
+
+
+    pub fn part_2(aoc_reader: AocBufReader) -> usize {
+        part_2_with_engine(aoc_reader, RebootEngine::SignedCuboids)
+    }
+
+
     #[cfg(test)]
     mod tests {
+        use std::collections::HashSet;
+
+        use itertools::Itertools;
+
+        use crate::utils::random::Lcg;
         use super::*;
 
+        const ENGINES: [RebootEngine; 2] = [RebootEngine::InclusionExclusion, RebootEngine::SignedCuboids];
+
 
         #[test]
         fn test_from_string() {
@@ -183,6 +234,105 @@ pub mod solutions {
         }
 
 
+        #[test]
+        fn test_reboot() {
+            let cubes = || vec![
+                "on x=10..12,y=10..12,z=10..12",
+                "on x=11..13,y=11..13,z=11..13",
+                "off x=9..11,y=9..11,z=9..11",
+                "on x=10..10,y=10..10,z=10..10",
+            ].into_iter().map(|s| Cube::from_string(s.to_string())).collect::<Vec<Cube>>();
+            for engine in ENGINES.iter() {
+                assert_eq!(_part_1(cubes(), *engine), 39);
+                assert_eq!(_part_2(cubes(), *engine), 39);
+            }
+        }
+
+
+        #[test]
+        fn test_initialization_region() {
+            let cubes = || vec![
+                "on x=-60..60,y=0..0,z=0..0",
+                "on x=100..200,y=100..200,z=100..200",
+                "off x=-10..-1,y=-20..20,z=0..0",
+                "off x=55..80,y=0..0,z=0..0",
+            ].into_iter().map(|s| Cube::from_string(s.to_string())).collect::<Vec<Cube>>();
+            for engine in ENGINES.iter() {
+                assert_eq!(_part_1(cubes(), *engine), 91);
+                assert_eq!(_part_2(cubes(), *engine), 121 + 101 * 101 * 101 - 10 - 6);
+            }
+        }
+
+
+        fn random_cube(rng: &mut Lcg) -> Cube {
+            let mut bounds = [0isize; 6];
+            for axis in 0..3 {
+                let a = rng.next_in(-12, 12);
+                let b = rng.next_in(-12, 12);
+                bounds[2 * axis] = a.min(b);
+                bounds[2 * axis + 1] = a.max(b);
+            }
+            Cube::new(
+                rng.next_in(0, 2) > 0,
+                bounds[0], bounds[1], bounds[2], bounds[3], bounds[4], bounds[5]
+            )
+        }
+
+
+        fn brute_force(cubes: &[Cube]) -> usize {
+            let mut lit: HashSet<[isize; 3]> = HashSet::new();
+            for cube in cubes {
+                let [x, y, z] = cube.region.ranges;
+                for point in (x.min..=x.max).cartesian_product(y.min..=y.max).cartesian_product(z.min..=z.max) {
+                    let ((px, py), pz) = point;
+                    if cube.on {
+                        lit.insert([px, py, pz]);
+                    } else {
+                        lit.remove(&[px, py, pz]);
+                    }
+                }
+            }
+            lit.len()
+        }
+
+
+        #[test]
+        fn test_engines_agree_on_random_cuboids() {
+            let mut rng = Lcg::new(2021);
+            for _ in 0..50 {
+                let n_cubes = rng.next_in(1, 12) as usize;
+                let cubes: Vec<Cube> = (0..n_cubes).map(|_| random_cube(&mut rng)).collect();
+                let expected = brute_force(&cubes);
+                for engine in ENGINES.iter() {
+                    assert_eq!(reboot(&cubes, *engine), expected, "{:?}", engine);
+                }
+            }
+        }
+
+
+        #[test]
+        fn test_signed_cuboids_on_nested_cubes() {
+            // Every cube contains the origin, so inclusion-exclusion would visit
+            // all 2^40 subsets; the signed engine keeps at most one entry per cube
+            let mut radii: Vec<isize> = (0..40).collect();
+            let mut rng = Lcg::new(22);
+            for idx in (1..radii.len()).rev() {
+                radii.swap(idx, rng.next_in(0, idx as isize) as usize);
+            }
+            let cubes: Vec<Cube> = radii.iter()
+                .map(|r| Cube::new(rng.next_in(0, 1) > 0, -r, *r, -r, *r, -r, *r))
+                .collect();
+
+            // The shell at distance d from the origin is set by the last cube reaching it
+            let expected: usize = (0..40isize).map(|d| {
+                let shell = (2 * d + 1).pow(3) - if d == 0 { 0 } else { (2 * d - 1).pow(3) };
+                let on = cubes.iter().rev().find(|cube| cube.region.ranges[0].max >= d).unwrap().on;
+                if on { shell as usize } else { 0 }
+            }).sum();
+            assert_eq!(reboot(&cubes, RebootEngine::SignedCuboids), expected);
+        }
+
+
         #[test]
         fn test_volume() {
             assert_eq!(Cube::from_string("on x=10..12,y=10..12,z=10..12".to_string()).boundary_volume(), 27);
@@ -1,58 +1,17 @@
 pub mod solutions {
-    use std::cmp::{max, min};
-    use std::collections::{HashMap, HashSet};
-
-    use lazy_static::lazy_static;
+    use std::cmp::{max, min, Reverse};
+    use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
 
     use crate::AocBufReader;
 
-    lazy_static! {
-        static ref ALL_SPACES: HashSet<Point> = vec![
-            Point::new(0, 0),
-            Point::new(1, 0),
-            Point::new(2, 0),
-            Point::new(2, 1),
-            Point::new(2, 2),
-            Point::new(3, 0),
-            Point::new(4, 0),
-            Point::new(4, 1),
-            Point::new(4, 2),
-            Point::new(5, 0),
-            Point::new(6, 0),
-            Point::new(6, 1),
-            Point::new(6, 2),
-            Point::new(7, 0),
-            Point::new(8, 0),
-            Point::new(8, 1),
-            Point::new(8, 2),
-            Point::new(9, 0),
-            Point::new(10, 0),
-        ].into_iter().collect();
-
-        static ref SPACES_OUTSIDE_ROOMS: HashSet<Point> = vec![
-            Point::new(2, 0),
-            Point::new(4, 0),
-            Point::new(6, 0),
-            Point::new(8, 1),
-        ].into_iter().collect();
-
-        static ref ROOMS_FOR_AMPHIPODS: HashMap<AmphipodColor, HashSet<Point>> = vec![
-            (AmphipodColor::A, vec![Point::new(2, 2), Point::new(2, 2)].into_iter().collect()),
-            (AmphipodColor::B, vec![Point::new(4, 2), Point::new(4, 2)].into_iter().collect()),
-            (AmphipodColor::C, vec![Point::new(6, 2), Point::new(6, 2)].into_iter().collect()),
-            (AmphipodColor::D, vec![Point::new(8, 2), Point::new(8, 2)].into_iter().collect()),
-        ].into_iter().collect();
-
-        static ref ROOM_NUMBERS: HashMap<AmphipodColor, usize> = vec![
-            (AmphipodColor::A, 2),
-            (AmphipodColor::B, 4),
-            (AmphipodColor::C, 6),
-            (AmphipodColor::D, 8),
-        ].into_iter().collect();
-    }
+    /// Rows unfolded from the diagram for part 2, inserted below the first room row
+    const PART_2_EXTRA_ROWS: [&str; 2] = [
+        "  #D#C#B#A#",
+        "  #D#B#A#C#",
+    ];
 
 
-    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
     struct Point {
         x: usize,
         y: usize
@@ -66,188 +25,398 @@ pub mod solutions {
     }
 
 
-    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-    enum AmphipodColor {
-        A,
-        B,
-        C,
-        D
+    /// Amphipods are labelled 'A', 'B', ... and the n-th letter belongs in the n-th room from the left
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct AmphipodColor(char);
+
+
+    impl AmphipodColor {
+        fn from_char(c: char) -> Option<AmphipodColor> {
+            match c.is_ascii_uppercase() {
+                true => Some(AmphipodColor(c)),
+                false => None
+            }
+        }
+
+        fn from_room_idx(room_idx: usize) -> AmphipodColor {
+            AmphipodColor((b'A' + room_idx as u8) as char)
+        }
+
+        fn room_idx(&self) -> usize {
+            (self.0 as u8 - b'A') as usize
+        }
     }
 
 
-    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-    struct Amphipod {
+    /// A single amphipod walking from one space to another
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Move {
         color: AmphipodColor,
-        location: Point,
-        must_move: bool,
-        can_move: bool
+        from: Point,
+        to: Point,
+        energy: usize,
     }
 
 
-    impl Amphipod {
-        fn new(color: AmphipodColor, location: Point) -> Amphipod {
-            Amphipod { color, location, must_move: false, can_move: true }
+    impl Move {
+        fn describe(&self) -> String {
+            format!(
+                "{} ({}, {}) -> ({}, {}): {} energy",
+                self.color.0, self.from.x, self.from.y, self.to.x, self.to.y, self.energy
+            )
         }
+    }
 
-        fn next_space_candidates(&self) -> Vec<Point> {
-            let north: Option<Point> = match self.location.y > 0 {
-                true => Some(Point::new(self.location.x, self.location.y - 1)),
-                false => None
-            };
-            let west: Option<Point> = match self.location.x > 0 {
-                true => Some(Point::new(self.location.x - 1, self.location.y)),
-                false => None
-            };
-            let east = Some(Point::new(self.location.x + 1, self.location.y));
-            let south = Some(Point::new(self.location.x, self.location.y + 1));
-            vec![north, east, south, west].into_iter().filter(|x| *x != None).map(|p| p.unwrap()).collect()
+
+    /// The cheapest way to organize a burrow: the moves in order, and the
+    /// state after each of them (states[0] is the starting position)
+    struct Solution {
+        energy: usize,
+        moves: Vec<Move>,
+        states: Vec<GameState>,
+    }
+
+
+    impl Solution {
+        fn print(&self, burrow: &Burrow) {
+            self.states[0].print(burrow);
+            for (step, state) in self.states.iter().skip(1).enumerate() {
+                println!();
+                println!("{}", self.moves[step].describe());
+                state.print(burrow);
+            }
+            println!();
+            println!("total energy: {}", self.energy);
         }
     }
 
 
-    #[derive(Debug, PartialEq, Eq, Clone)]
-    struct GameState {
-        amphipods: HashSet<Amphipod>,
-        cost: usize,
+    /// The shape of the burrow as read from the diagram: the hallway runs along
+    /// y = 0 and each room hangs below it from y = 1 (nearest the hallway) to
+    /// y = room_depth. Rooms are listed left to right, so room_xs[n] is the
+    /// column of the room that the n-th color calls home.
+    struct Burrow {
+        hallway_length: usize,
+        room_xs: Vec<usize>,
+        room_depth: usize,
+        energy_per_step: Vec<usize>,
     }
 
 
-    impl GameState {
-        fn from_vec(
-            A: Vec<Point>, B: Vec<Point>,
-            C: Vec<Point>, D: Vec<Point>,
-            cost: usize
-        )  -> GameState {
-            GameState {
-                amphipods: vec![
-                    Amphipod::new(AmphipodColor::A, A[0]),
-                    Amphipod::new(AmphipodColor::A, A[1]),
-                    Amphipod::new(AmphipodColor::B, B[0]),
-                    Amphipod::new(AmphipodColor::B, B[1]),
-                    Amphipod::new(AmphipodColor::C, C[0]),
-                    Amphipod::new(AmphipodColor::C, C[1]),
-                    Amphipod::new(AmphipodColor::D, D[0]),
-                    Amphipod::new(AmphipodColor::D, D[1])
-                ].into_iter().collect(),
-                cost: cost
-            }
+    impl Burrow {
+        /// Replaces the default costs of 1, 10, 100, ... with one cost per room, left to right
+        fn with_energy_costs(mut self, energy_per_step: Vec<usize>) -> Burrow {
+            assert_eq!(energy_per_step.len(), self.room_xs.len(), "need one energy cost per room");
+            self.energy_per_step = energy_per_step;
+            self
         }
 
-        fn space_occupant(&self, space: &Point) -> Option<&Amphipod> {
-            let amphipods_in_space: Vec<&Amphipod> =  self.amphipods.iter().filter(|a| a.location == *space).collect();
-            match amphipods_in_space.len() {
-                0 => None,
-                1 => Some(&amphipods_in_space[0]),
-                _ => panic!("Too many amphipods in this space! Get a room!")
-            }
+        fn room_x(&self, color: &AmphipodColor) -> usize {
+            self.room_xs[color.room_idx()]
+        }
+
+        fn energy_per_step(&self, color: &AmphipodColor) -> usize {
+            self.energy_per_step[color.room_idx()]
+        }
+
+        fn hallway(&self) -> Vec<Point> {
+            (0..self.hallway_length).map(|x| Point::new(x, 0)).collect()
+        }
+
+        fn is_outside_room(&self, space: &Point) -> bool {
+            space.y == 0 && self.room_xs.contains(&space.x)
         }
 
-        fn space_is_occupied_by_another_color(&self, amphipod: &Amphipod, space: &Point) -> bool {
-            match self.space_occupant(space) {
-                Some(other) => other.color != amphipod.color,
-                None => false
+        fn room_spaces(&self, color: &AmphipodColor) -> Vec<Point> {
+            let x = self.room_x(color);
+            (1..=self.room_depth).map(|y| Point::new(x, y)).collect()
+        }
+
+        fn all_spaces(&self) -> HashSet<Point> {
+            let mut spaces: HashSet<Point> = self.hallway().into_iter().collect();
+            for room_idx in 0..self.room_xs.len() {
+                spaces.extend(self.room_spaces(&AmphipodColor::from_room_idx(room_idx)));
             }
+            spaces
+        }
+    }
+
+
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct GameState {
+        amphipods: BTreeMap<Point, AmphipodColor>,
+    }
+
+
+    impl GameState {
+        fn space_occupant(&self, space: &Point) -> Option<AmphipodColor> {
+            self.amphipods.get(space).copied()
         }
 
         fn get_occupied_spaces(&self) -> HashSet<Point> {
-            self.amphipods.iter().map(|a| a.location).collect()
+            self.amphipods.keys().copied().collect()
         }
 
-        fn get_unoccupied_spaces(&self) -> HashSet<Point> {
-            let all_spaces: HashSet<Point> = ALL_SPACES.iter().map(|p| *p).collect();
-            all_spaces.into_iter().filter(|p| !self.get_occupied_spaces().contains(&p)).collect()
+        fn get_unoccupied_spaces(&self, burrow: &Burrow) -> HashSet<Point> {
+            burrow.all_spaces().difference(&self.get_occupied_spaces()).copied().collect()
         }
 
-        fn can_amphipod_move_to_space(&self, amphipod: &Amphipod, candidate: &Point) -> bool {
-            if let Some(occupant) = self.space_occupant(candidate) {
-                return false
-            }
+        /// True if every hallway space between from_x (exclusive) and to_x (inclusive) is free
+        fn hallway_is_clear(&self, from_x: usize, to_x: usize) -> bool {
+            let (lo, hi) = (min(from_x, to_x), max(from_x, to_x));
+            !(lo..=hi).any(|x| x != from_x && self.space_occupant(&Point::new(x, 0)).is_some())
+        }
+
+        fn room_has_other_colors(&self, burrow: &Burrow, color: &AmphipodColor) -> bool {
+            burrow.room_spaces(color).iter().any(|space| {
+                match self.space_occupant(space) {
+                    Some(occupant) => occupant != *color,
+                    None => false
+                }
+            })
+        }
 
-            for (room_color, room_spaces) in ROOMS_FOR_AMPHIPODS.iter() {
-                if room_spaces.contains(candidate) {
-                    if *room_color != amphipod.color {
-                        // can only move in another's room if it's already there
-                        return room_spaces.contains(&amphipod.location)
+        /// An amphipod is settled once it is in its own room with only its own color beneath it
+        fn is_settled(&self, burrow: &Burrow, location: &Point, color: &AmphipodColor) -> bool {
+            location.y > 0 && location.x == burrow.room_x(color) && (location.y + 1..=burrow.room_depth).all(
+                |y| self.space_occupant(&Point::new(location.x, y)) == Some(*color)
+            )
+        }
+
+        fn is_organized(&self, burrow: &Burrow) -> bool {
+            self.amphipods.iter().all(|(location, color)| self.is_settled(burrow, location, color))
+        }
+
+        fn moved(&self, from: &Point, to: &Point) -> GameState {
+            let mut amphipods = self.amphipods.clone();
+            let color = amphipods.remove(from).unwrap();
+            amphipods.insert(*to, color);
+            GameState { amphipods }
+        }
+
+        fn apply(&self, m: &Move) -> GameState {
+            self.moved(&m.from, &m.to)
+        }
+
+        /// Draws the burrow in the same ASCII form as the puzzle input
+        fn render(&self, burrow: &Burrow) -> String {
+            let width = burrow.hallway_length + 2;
+            let cell = |space: Point| match self.space_occupant(&space) {
+                Some(color) => color.0,
+                None => '.'
+            };
+            let first_room_col = burrow.room_xs[0];
+            let last_room_col = burrow.room_xs[burrow.room_xs.len() - 1] + 2;
+
+            let mut rows: Vec<String> = vec!["#".repeat(width)];
+            rows.push(format!("#{}#", (0..burrow.hallway_length).map(|x| cell(Point::new(x, 0))).collect::<String>()));
+            for y in 1..=burrow.room_depth {
+                rows.push((0..width).filter_map(|col| {
+                    if col > 0 && burrow.room_xs.contains(&(col - 1)) {
+                        Some(cell(Point::new(col - 1, y)))
+                    } else if y == 1 || (first_room_col <= col && col <= last_room_col) {
+                        Some('#')
+                    } else if col < first_room_col {
+                        Some(' ')
                     } else {
-                        // can only enter its own room if it's not occupied by another color
-                        return !room_spaces.iter().any(|space| self.space_is_occupied_by_another_color(amphipod, space))
+                        None
                     }
-                }
+                }).collect());
             }
-            // it's unoccupied hallway
-            true
+            rows.push(format!("{}{}", " ".repeat(first_room_col), "#".repeat(last_room_col - first_room_col + 1)));
+            rows.join("\n")
         }
 
-        fn this_amphipods_room_is_occupied_by_other(&self, amphipod: &Amphipod) -> bool {
-            ROOMS_FOR_AMPHIPODS.get(&amphipod.color).unwrap().iter().any(
-                |space| self.space_is_occupied_by_another_color(amphipod, space)
-            )
+        fn print(&self, burrow: &Burrow) {
+            println!("{}", self.render(burrow));
         }
 
-        fn can_this_amphipod_go_home(&self, amphipod: &Amphipod) -> bool {
-            if self.this_amphipods_room_is_occupied_by_other(amphipod) {
-                return false
+        /// Where this amphipod would stop if it went home now, if the way is open
+        fn home_destination(&self, burrow: &Burrow, location: &Point, color: &AmphipodColor) -> Option<Point> {
+            if self.room_has_other_colors(burrow, color) {
+                return None
             }
+            if !self.hallway_is_clear(location.x, burrow.room_x(color)) {
+                return None
+            }
+            burrow.room_spaces(color).into_iter().rev().find(|space| self.space_occupant(space).is_none())
+        }
 
-            let min_x: usize = min(*ROOM_NUMBERS.get(&amphipod.color).unwrap(), amphipod.location.x);
-            let max_x: usize = max(*ROOM_NUMBERS.get(&amphipod.color).unwrap(), amphipod.location.x);
+        fn can_leave_room(&self, burrow: &Burrow, location: &Point, color: &AmphipodColor) -> bool {
+            location.y > 0
+                && !self.is_settled(burrow, location, color)
+                && !(1..location.y).any(|y| self.space_occupant(&Point::new(location.x, y)).is_some())
+        }
 
-            !(min_x..=max_x).any(|x| self.space_occupant(&Point::new(x, 0)) != None)
+        /// Every state reachable with a single legal move, with the move that gets there.
+        /// Amphipods in a room step out into the hallway; amphipods may only step
+        /// into their own room once it holds no other colors. Going home is never
+        /// worse than any alternative, so if anyone can, that is the only move offered.
+        fn get_neighbors(&self, burrow: &Burrow) -> Vec<(GameState, Move)> {
+            for (location, color) in self.amphipods.iter() {
+                if location.y > 0 && !self.can_leave_room(burrow, location, color) {
+                    continue
+                }
+                if let Some(home) = self.home_destination(burrow, location, color) {
+                    let steps = location.y + max(location.x, home.x) - min(location.x, home.x) + home.y;
+                    let m = Move { color: *color, from: *location, to: home, energy: steps * burrow.energy_per_step(color) };
+                    return vec![(self.apply(&m), m)]
+                }
+            }
+
+            let mut neighbors: Vec<(GameState, Move)> = vec![];
+            for (location, color) in self.amphipods.iter() {
+                if !self.can_leave_room(burrow, location, color) {
+                    continue
+                }
+                for space in burrow.hallway().iter() {
+                    if burrow.is_outside_room(space) || !self.hallway_is_clear(location.x, space.x) {
+                        continue
+                    }
+                    let steps = location.y + max(location.x, space.x) - min(location.x, space.x);
+                    let m = Move { color: *color, from: *location, to: *space, energy: steps * burrow.energy_per_step(color) };
+                    neighbors.push((self.apply(&m), m));
+                }
+            }
+            neighbors
         }
+    }
+
+
+    /// Walks the recorded predecessors back from the organized state to the start
+    fn trace_back(
+        mut state: GameState, energy: usize, previous: &HashMap<GameState, (GameState, Move)>
+    ) -> Solution {
+        let mut moves: Vec<Move> = vec![];
+        let mut states: Vec<GameState> = vec![state.clone()];
+        while let Some((prior, m)) = previous.get(&state) {
+            moves.push(*m);
+            states.push(prior.clone());
+            state = prior.clone();
+        }
+        moves.reverse();
+        states.reverse();
+        Solution { energy, moves, states }
+    }
+
 
-        fn this_amphipods_home(&self, amphipod: &Amphipod) -> Point {
-            let first_spot = Point::new(*ROOM_NUMBERS.get(&amphipod.color).unwrap(),2);
-            let second_spot = Point::new(*ROOM_NUMBERS.get(&amphipod.color).unwrap(),1);
-            if self.space_occupant(&first_spot) != None {
-                second_spot
-            } else {
-                first_spot
+    /// Dijkstra over game states; returns the cheapest sequence of moves that organizes the burrow
+    fn organize(burrow: &Burrow, start: GameState) -> Option<Solution> {
+        let mut best: HashMap<GameState, usize> = HashMap::new();
+        let mut previous: HashMap<GameState, (GameState, Move)> = HashMap::new();
+        let mut queue: BinaryHeap<Reverse<(usize, GameState)>> = BinaryHeap::new();
+        best.insert(start.clone(), 0);
+        queue.push(Reverse((0, start)));
+
+        while let Some(Reverse((energy, state))) = queue.pop() {
+            if state.is_organized(burrow) {
+                return Some(trace_back(state, energy, &previous))
+            }
+            if energy > *best.get(&state).unwrap_or(&usize::MAX) {
+                continue
+            }
+            for (neighbor, m) in state.get_neighbors(burrow) {
+                let candidate = energy + m.energy;
+                if candidate < *best.get(&neighbor).unwrap_or(&usize::MAX) {
+                    best.insert(neighbor.clone(), candidate);
+                    previous.insert(neighbor.clone(), (state.clone(), m));
+                    queue.push(Reverse((candidate, neighbor)));
+                }
             }
         }
+        None
+    }
+
+
+    /// The least energy that organizes the burrow
+    fn minimum_energy(burrow: &Burrow, start: GameState) -> Option<usize> {
+        organize(burrow, start).map(|solution| solution.energy)
+    }
 
-        fn get_neighbors(&self) -> Vec<GameState> {
-            let mut neighbors: Vec<GameState> = vec![];
-            // If any amphipod _must_ move; move it!
-            let must_move: Vec<&Amphipod> = self.amphipods.iter().filter(|x| x.must_move).collect();
-            // match must_move.len() {
-            //     0 => (),
-            //     1 => {
-            //         let amphipod = must_move[0];
-            //         return amphipod.next_space_candidates().into_iter()
-            //             .filter(|p| self.can_amphipod_move_to_space(amphipod, p))
-            //             .map(|p| {
-            //                 // let mut result = self.clone();
-            //                 // result.amphipods
-            //             })
-            //             // .collect()
-            //     },
-            //     _ => panic!("More than one amphipod must move!")
-            // }
 
+    /// Reads the ASCII burrow diagram. The second line is the hallway and every
+    /// open space ('.' or a letter) below it is part of a room; the diagram's
+    /// walls are one column to the left of hallway x = 0.
+    fn read_input(lines: Vec<String>) -> (Burrow, GameState) {
+        let is_open = |c: char| c == '.' || AmphipodColor::from_char(c).is_some();
+
+        let hallway_length = lines[1].chars().filter(|c| is_open(*c)).count();
+        let mut room_xs: Vec<usize> = vec![];
+        let mut room_depth: usize = 0;
+        let mut amphipods: BTreeMap<Point, AmphipodColor> = BTreeMap::new();
+        for (row_idx, line) in lines.iter().enumerate().skip(1) {
+            for (col_idx, c) in line.chars().enumerate() {
+                if !is_open(c) {
+                    continue
+                }
+                let space = Point::new(col_idx - 1, row_idx - 1);
+                if space.y > 0 {
+                    room_depth = max(room_depth, space.y);
+                    if !room_xs.contains(&space.x) {
+                        room_xs.push(space.x);
+                    }
+                }
+                if let Some(color) = AmphipodColor::from_char(c) {
+                    amphipods.insert(space, color);
+                }
+            }
+        }
+        room_xs.sort();
 
-            // if any amphipod can go home from this state, move them there.
+        let energy_per_step = (0..room_xs.len() as u32).map(|idx| 10usize.pow(idx)).collect();
+        (Burrow { hallway_length, room_xs, room_depth, energy_per_step }, GameState { amphipods })
+    }
 
-            // append all other legal moves
 
-            neighbors
+    fn unfold(mut lines: Vec<String>) -> Vec<String> {
+        for (offset, row) in PART_2_EXTRA_ROWS.iter().enumerate() {
+            lines.insert(3 + offset, row.to_string());
         }
+        lines
     }
 
 
-    fn part_1_test_input() -> GameState {
-        GameState::from_vec(
-            vec![Point::new(2, 2), Point::new(8, 2)],
-            vec![Point::new(2, 1), Point::new(6, 1)],
-            vec![Point::new(4, 1), Point::new(6, 2)],
-            vec![Point::new(4, 2), Point::new(8, 1)],
-            0
-        )
+    pub fn part_1(aoc_reader: AocBufReader) -> usize {
+        let (burrow, start) = read_input(aoc_reader.collect());
+        minimum_energy(&burrow, start).unwrap()
     }
 
 
-    pub fn part_1() -> usize {
-        let start = part_1_test_input();
-        1
+    /// Part 1 with a custom energy cost per step for each color, 'A' first
+    pub fn part_1_with_energy_costs(aoc_reader: AocBufReader, energy_per_step: Vec<usize>) -> usize {
+        let (burrow, start) = read_input(aoc_reader.collect());
+        minimum_energy(&burrow.with_energy_costs(energy_per_step), start).unwrap()
+    }
+
+
+    pub fn part_2(aoc_reader: AocBufReader) -> usize {
+        let (burrow, start) = read_input(unfold(aoc_reader.collect()));
+        minimum_energy(&burrow, start).unwrap()
+    }
+
+
+    /// Prints every intermediate burrow along the cheapest part 1 solution
+    pub fn replay_part_1(aoc_reader: AocBufReader) -> usize {
+        let (burrow, start) = read_input(aoc_reader.collect());
+        let solution = organize(&burrow, start).unwrap();
+        solution.print(&burrow);
+        solution.energy
+    }
+
+
+    /// Prints every intermediate burrow along the cheapest part 2 solution
+    pub fn replay_part_2(aoc_reader: AocBufReader) -> usize {
+        let (burrow, start) = read_input(unfold(aoc_reader.collect()));
+        let solution = organize(&burrow, start).unwrap();
+        solution.print(&burrow);
+        solution.energy
+    }
+
+
+    /// Part 2 with a custom energy cost per step for each color, 'A' first
+    pub fn part_2_with_energy_costs(aoc_reader: AocBufReader, energy_per_step: Vec<usize>) -> usize {
+        let (burrow, start) = read_input(unfold(aoc_reader.collect()));
+        minimum_energy(&burrow.with_energy_costs(energy_per_step), start).unwrap()
     }
 
 
@@ -255,11 +424,142 @@ pub mod solutions {
     mod tests {
         use super::*;
 
+        fn part_1_test_input() -> Vec<String> {
+            vec![
+                "#############",
+                "#...........#",
+                "###B#C#B#D###",
+                "  #A#D#C#A#",
+                "  #########",
+            ].into_iter().map(|s| s.to_string()).collect()
+        }
+
+        fn small_test_input() -> Vec<String> {
+            vec![
+                "#######",
+                "#.....#",
+                "##B#A##",
+                " #####",
+            ].into_iter().map(|s| s.to_string()).collect()
+        }
+
+        #[test]
+        fn test_geometry() {
+            let (burrow, _) = read_input(part_1_test_input());
+            assert_eq!(burrow.hallway_length, 11);
+            assert_eq!(burrow.room_xs, vec![2, 4, 6, 8]);
+            assert_eq!(burrow.energy_per_step, vec![1, 10, 100, 1000]);
+
+            let (burrow, start) = read_input(small_test_input());
+            assert_eq!(burrow.hallway_length, 5);
+            assert_eq!(burrow.room_xs, vec![1, 3]);
+            assert_eq!(burrow.room_depth, 1);
+            assert_eq!(start.get_unoccupied_spaces(&burrow).len(), 5);
+        }
+
+        #[test]
+        fn test_small_burrow() {
+            // A steps aside to x = 4 (2), B walks home (40), A walks home (4)
+            let (burrow, start) = read_input(small_test_input());
+            assert_eq!(minimum_energy(&burrow, start), Some(46));
+
+            // when A is the expensive one it should only ever take the direct route home
+            let (burrow, start) = read_input(small_test_input());
+            assert_eq!(minimum_energy(&burrow.with_energy_costs(vec![1000, 1]), start), Some(4006));
+        }
+
+        #[test]
         fn test_occupied() {
-            let start = part_1_test_input();
+            let (burrow, start) = read_input(part_1_test_input());
+            assert_eq!(burrow.room_depth, 2);
             assert_eq!(start.get_occupied_spaces().len(), 8);
-            assert_eq!(start.get_unoccupied_spaces().len(), 11);
+            assert_eq!(start.get_unoccupied_spaces(&burrow).len(), 11);
+            assert_eq!(start.space_occupant(&Point::new(2, 1)), Some(AmphipodColor('B')));
+            assert_eq!(start.space_occupant(&Point::new(8, 2)), Some(AmphipodColor('A')));
+        }
+
+        #[test]
+        fn test_settled() {
+            let (burrow, start) = read_input(part_1_test_input());
+            assert!(start.is_settled(&burrow, &Point::new(2, 2), &AmphipodColor('A')));
+            assert!(start.is_settled(&burrow, &Point::new(6, 2), &AmphipodColor('C')));
+            assert!(!start.is_settled(&burrow, &Point::new(2, 1), &AmphipodColor('B')));
+            assert!(!start.is_organized(&burrow));
+        }
+
+        #[test]
+        fn test_neighbors() {
+            let (burrow, start) = read_input(part_1_test_input());
+            // only the four top amphipods can leave, each to any of the 7 hallway stops
+            assert_eq!(start.get_neighbors(&burrow).len(), 4 * 7);
+
+            let d_out = start.moved(&Point::new(8, 1), &Point::new(10, 0));
+            let a_out = d_out.moved(&Point::new(8, 2), &Point::new(9, 0));
+            let b_out = a_out.moved(&Point::new(6, 1), &Point::new(3, 0));
+            // with room C's B out of the way the C above D in room B can walk straight home
+            let c_home = b_out.get_neighbors(&burrow);
+            assert_eq!(c_home.len(), 1);
+            assert_eq!(c_home[0].0.space_occupant(&Point::new(6, 1)), Some(AmphipodColor('C')));
+            assert_eq!(c_home[0].1, Move {
+                color: AmphipodColor('C'), from: Point::new(4, 1), to: Point::new(6, 1), energy: 400
+            });
         }
 
+        #[test]
+        fn test_render() {
+            let (burrow, start) = read_input(part_1_test_input());
+            assert_eq!(start.render(&burrow), part_1_test_input().join("\n"));
+
+            let (burrow, start) = read_input(unfold(part_1_test_input()));
+            assert_eq!(start.render(&burrow), unfold(part_1_test_input()).join("\n"));
+
+            let (burrow, start) = read_input(small_test_input());
+            assert_eq!(start.render(&burrow), small_test_input().join("\n"));
+
+            let (burrow, start) = read_input(part_1_test_input());
+            let stepped_out = start.moved(&Point::new(6, 1), &Point::new(3, 0));
+            assert_eq!(stepped_out.render(&burrow), [
+                "#############",
+                "#...B.......#",
+                "###B#C#.#D###",
+                "  #A#D#C#A#",
+                "  #########",
+            ].join("\n"));
+        }
+
+        #[test]
+        fn test_replay() {
+            let (burrow, start) = read_input(part_1_test_input());
+            let solution = organize(&burrow, start.clone()).unwrap();
+            assert_eq!(solution.energy, 12521);
+            assert_eq!(solution.moves.len() + 1, solution.states.len());
+            assert_eq!(solution.moves.iter().map(|m| m.energy).sum::<usize>(), 12521);
+
+            let mut state = start;
+            for (m, expected) in solution.moves.iter().zip(solution.states.iter().skip(1)) {
+                state = state.apply(m);
+                assert_eq!(&state, expected);
+            }
+            assert_eq!(state.render(&burrow), [
+                "#############",
+                "#...........#",
+                "###A#B#C#D###",
+                "  #A#B#C#D#",
+                "  #########",
+            ].join("\n"));
+        }
+
+        #[test]
+        fn test_part_1() {
+            let (burrow, start) = read_input(part_1_test_input());
+            assert_eq!(minimum_energy(&burrow, start), Some(12521));
+        }
+
+        #[test]
+        fn test_part_2() {
+            let (burrow, start) = read_input(unfold(part_1_test_input()));
+            assert_eq!(burrow.room_depth, 4);
+            assert_eq!(minimum_energy(&burrow, start), Some(44169));
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,393 @@
+pub mod solutions {
+    use crate::AocBufReader;
+
+    const N_MODEL_NUMBER_DIGITS: usize = 14;
+
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Register {
+        W,
+        X,
+        Y,
+        Z,
+    }
+
+
+    impl Register {
+        fn from_str(s: &str) -> Option<Register> {
+            match s {
+                "w" => Some(Register::W),
+                "x" => Some(Register::X),
+                "y" => Some(Register::Y),
+                "z" => Some(Register::Z),
+                _ => None
+            }
+        }
+
+        fn idx(&self) -> usize {
+            match self {
+                Register::W => 0,
+                Register::X => 1,
+                Register::Y => 2,
+                Register::Z => 3,
+            }
+        }
+    }
+
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Operand {
+        Register(Register),
+        Literal(isize),
+    }
+
+
+    impl Operand {
+        fn from_str(s: &str) -> Option<Operand> {
+            match Register::from_str(s) {
+                Some(register) => Some(Operand::Register(register)),
+                None => s.parse::<isize>().ok().map(Operand::Literal)
+            }
+        }
+    }
+
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Instruction {
+        Inp(Register),
+        Add(Register, Operand),
+        Mul(Register, Operand),
+        Div(Register, Operand),
+        Mod(Register, Operand),
+        Eql(Register, Operand),
+    }
+
+
+    impl Instruction {
+        fn from_string(s: &str) -> Instruction {
+            let tokens: Vec<&str> = s.split_whitespace().collect();
+            let register = Register::from_str(tokens[1]).unwrap();
+            if tokens[0] == "inp" {
+                return Instruction::Inp(register)
+            }
+
+            let operand = Operand::from_str(tokens[2]).unwrap();
+            match tokens[0] {
+                "add" => Instruction::Add(register, operand),
+                "mul" => Instruction::Mul(register, operand),
+                "div" => Instruction::Div(register, operand),
+                "mod" => Instruction::Mod(register, operand),
+                "eql" => Instruction::Eql(register, operand),
+                _ => panic!("unknown ALU instruction {}", s)
+            }
+        }
+    }
+
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum AluError {
+        /// `inp` ran with no input values left
+        MissingInput { pc: usize },
+        /// `div` by zero
+        DivideByZero { pc: usize },
+        /// `mod` with a negative dividend or non-positive divisor
+        InvalidModulo { pc: usize },
+    }
+
+
+    #[derive(Debug, Default, PartialEq, Eq)]
+    struct Alu {
+        registers: [isize; 4],
+    }
+
+
+    impl Alu {
+        fn get(&self, register: Register) -> isize {
+            self.registers[register.idx()]
+        }
+
+        fn value(&self, operand: Operand) -> isize {
+            match operand {
+                Operand::Register(register) => self.get(register),
+                Operand::Literal(val) => val
+            }
+        }
+
+        /// Runs program to completion, reading one input value per `inp`
+        fn run(&mut self, program: &[Instruction], inputs: &[isize]) -> Result<(), AluError> {
+            let mut inputs = inputs.iter();
+            for (pc, instruction) in program.iter().enumerate() {
+                let (register, result) = match *instruction {
+                    Instruction::Inp(a) => (a, *inputs.next().ok_or(AluError::MissingInput { pc })?),
+                    Instruction::Add(a, b) => (a, self.get(a) + self.value(b)),
+                    Instruction::Mul(a, b) => (a, self.get(a) * self.value(b)),
+                    Instruction::Div(a, b) => {
+                        let divisor = self.value(b);
+                        if divisor == 0 {
+                            return Err(AluError::DivideByZero { pc })
+                        }
+                        (a, self.get(a) / divisor)
+                    },
+                    Instruction::Mod(a, b) => {
+                        let divisor = self.value(b);
+                        if self.get(a) < 0 || divisor <= 0 {
+                            return Err(AluError::InvalidModulo { pc })
+                        }
+                        (a, self.get(a) % divisor)
+                    },
+                    Instruction::Eql(a, b) => (a, (self.get(a) == self.value(b)) as isize),
+                };
+                self.registers[register.idx()] = result;
+            }
+            Ok(())
+        }
+    }
+
+
+    /// The three constants that distinguish one of MONAD's 14 per-digit blocks.
+    /// Every block computes, with w the digit and z treated as a base-26 stack:
+    ///
+    ///     x = (z % 26 + add_x != w)
+    ///     z = z / div_z
+    ///     if x { z = 26 * z + w + add_y }
+    ///
+    /// Blocks with div_z == 1 have add_x > 9 so they always push w + add_y.
+    /// Blocks with div_z == 26 pop, and only avoid pushing again when the
+    /// digit equals the popped value plus add_x.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct MonadBlock {
+        div_z: isize,
+        add_x: isize,
+        add_y: isize,
+    }
+
+
+    impl MonadBlock {
+        fn from_instructions(block: &[Instruction]) -> Option<MonadBlock> {
+            let div_idx = block.iter().position(|i| matches!(i, Instruction::Div(Register::Z, Operand::Literal(_))))?;
+            let div_z = match block[div_idx] { Instruction::Div(_, Operand::Literal(v)) => v, _ => return None };
+            let add_x = match block.get(div_idx + 1)? { Instruction::Add(Register::X, Operand::Literal(v)) => *v, _ => return None };
+
+            let add_w_idx = block.iter().position(|i| *i == Instruction::Add(Register::Y, Operand::Register(Register::W)))?;
+            let add_y = match block.get(add_w_idx + 1)? { Instruction::Add(Register::Y, Operand::Literal(v)) => *v, _ => return None };
+
+            Some(MonadBlock { div_z, add_x, add_y })
+        }
+    }
+
+
+    /// Splits the program at each `inp` and reads the constants out of every block
+    fn analyze(program: &[Instruction]) -> Option<Vec<MonadBlock>> {
+        let starts: Vec<usize> = program.iter().enumerate()
+            .filter(|(_, i)| matches!(i, Instruction::Inp(_)))
+            .map(|(idx, _)| idx)
+            .collect();
+        if starts.len() != N_MODEL_NUMBER_DIGITS {
+            return None
+        }
+        (0..starts.len()).map(|n| {
+            let end = if n + 1 < starts.len() { starts[n + 1] } else { program.len() };
+            MonadBlock::from_instructions(&program[starts[n]..end])
+        }).collect()
+    }
+
+
+    /// Pairs each pushing block with the block that pops it. z ends at 0 exactly
+    /// when every pair satisfies digits[pop] == digits[push] + offset, where
+    /// offset is the pushing block's add_y plus the popping block's add_x.
+    fn digit_constraints(blocks: &[MonadBlock]) -> Option<Vec<(usize, usize, isize)>> {
+        let mut stack: Vec<usize> = vec![];
+        let mut constraints: Vec<(usize, usize, isize)> = vec![];
+        for (idx, block) in blocks.iter().enumerate() {
+            match block.div_z {
+                1 => stack.push(idx),
+                26 => {
+                    let push_idx = stack.pop()?;
+                    constraints.push((push_idx, idx, blocks[push_idx].add_y + block.add_x));
+                },
+                _ => return None
+            }
+        }
+        match stack.is_empty() {
+            true => Some(constraints),
+            false => None
+        }
+    }
+
+
+    /// Picks each constrained pair of digits as large (or small) as possible
+    fn extreme_model_number(constraints: &[(usize, usize, isize)], largest: bool) -> Option<Vec<isize>> {
+        let mut digits: Vec<isize> = vec![0; N_MODEL_NUMBER_DIGITS];
+        for (push_idx, pop_idx, offset) in constraints {
+            let push_digit = match largest {
+                true => 9.min(9 - offset),
+                false => 1.max(1 - offset)
+            };
+            if !(1..=9).contains(&push_digit) || !(1..=9).contains(&(push_digit + offset)) {
+                return None
+            }
+            digits[*push_idx] = push_digit;
+            digits[*pop_idx] = push_digit + offset;
+        }
+        Some(digits)
+    }
+
+
+    fn accepts(program: &[Instruction], digits: &[isize]) -> bool {
+        let mut alu = Alu::default();
+        alu.run(program, digits).is_ok() && alu.get(Register::Z) == 0
+    }
+
+
+    fn digits_to_usize(digits: &[isize]) -> usize {
+        digits.iter().fold(0, |acc, d| acc * 10 + *d as usize)
+    }
+
+
+    /// Finds the largest or smallest model number MONAD accepts by reading
+    /// the digit constraints off the program, then confirms it on the ALU
+    fn solve(program: &[Instruction], largest: bool) -> Option<usize> {
+        let constraints = digit_constraints(&analyze(program)?)?;
+        let digits = extreme_model_number(&constraints, largest)?;
+        match accepts(program, &digits) {
+            true => Some(digits_to_usize(&digits)),
+            false => None
+        }
+    }
+
+
+    fn read_input(aoc_reader: AocBufReader) -> Vec<Instruction> {
+        aoc_reader.filter(|line| !line.is_empty()).map(|line| Instruction::from_string(&line)).collect()
+    }
+
+
+    pub fn part_1(aoc_reader: AocBufReader) -> usize {
+        solve(&read_input(aoc_reader), true).unwrap()
+    }
+
+
+    pub fn part_2(aoc_reader: AocBufReader) -> usize {
+        solve(&read_input(aoc_reader), false).unwrap()
+    }
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn parse(lines: &[&str]) -> Vec<Instruction> {
+            lines.iter().map(|line| Instruction::from_string(line)).collect()
+        }
+
+        fn monad_block(div_z: isize, add_x: isize, add_y: isize) -> Vec<String> {
+            vec![
+                "inp w".to_string(),
+                "mul x 0".to_string(),
+                "add x z".to_string(),
+                "mod x 26".to_string(),
+                format!("div z {}", div_z),
+                format!("add x {}", add_x),
+                "eql x w".to_string(),
+                "eql x 0".to_string(),
+                "mul y 0".to_string(),
+                "add y 25".to_string(),
+                "mul y x".to_string(),
+                "add y 1".to_string(),
+                "mul z y".to_string(),
+                "mul y 0".to_string(),
+                "add y w".to_string(),
+                format!("add y {}", add_y),
+                "mul y x".to_string(),
+                "add z y".to_string(),
+            ]
+        }
+
+        fn test_monad() -> Vec<Instruction> {
+            let blocks: Vec<(isize, isize, isize)> = vec![
+                (1, 11, 12),
+                (1, 13, 7),
+                (26, -10, 4),
+                (1, 10, 1),
+                (1, 14, 4),
+                (26, -2, 9),
+                (26, 0, 3),
+                (1, 12, 3),
+                (26, -8, 2),
+                (1, 11, 0),
+                (1, 15, 15),
+                (26, -15, 8),
+                (26, -4, 6),
+                (26, -9, 1),
+            ];
+            let lines: Vec<String> = blocks.into_iter().flat_map(|(d, x, y)| monad_block(d, x, y)).collect();
+            lines.iter().map(|line| Instruction::from_string(line)).collect()
+        }
+
+        #[test]
+        fn test_parse() {
+            assert_eq!(Instruction::from_string("inp w"), Instruction::Inp(Register::W));
+            assert_eq!(
+                Instruction::from_string("mul x -1"),
+                Instruction::Mul(Register::X, Operand::Literal(-1))
+            );
+            assert_eq!(
+                Instruction::from_string("eql z x"),
+                Instruction::Eql(Register::Z, Operand::Register(Register::X))
+            );
+        }
+
+        #[test]
+        fn test_alu_examples() {
+            let negate = parse(&["inp x", "mul x -1"]);
+            let mut alu = Alu::default();
+            alu.run(&negate, &[7]).unwrap();
+            assert_eq!(alu.get(Register::X), -7);
+
+            let three_times = parse(&["inp z", "inp x", "mul z 3", "eql z x"]);
+            let mut alu = Alu::default();
+            alu.run(&three_times, &[3, 9]).unwrap();
+            assert_eq!(alu.get(Register::Z), 1);
+            let mut alu = Alu::default();
+            alu.run(&three_times, &[3, 8]).unwrap();
+            assert_eq!(alu.get(Register::Z), 0);
+
+            let binary = parse(&[
+                "inp w", "add z w", "mod z 2", "div w 2", "add y w", "mod y 2",
+                "div w 2", "add x w", "mod x 2", "div w 2", "mod w 2",
+            ]);
+            let mut alu = Alu::default();
+            alu.run(&binary, &[11]).unwrap();
+            assert_eq!(alu.registers, [1, 0, 1, 1]);
+        }
+
+        #[test]
+        fn test_alu_errors() {
+            assert_eq!(Alu::default().run(&parse(&["inp w", "inp x"]), &[1]), Err(AluError::MissingInput { pc: 1 }));
+            assert_eq!(Alu::default().run(&parse(&["div w 0"]), &[]), Err(AluError::DivideByZero { pc: 0 }));
+            assert_eq!(Alu::default().run(&parse(&["add w -1", "mod w 3"]), &[]), Err(AluError::InvalidModulo { pc: 1 }));
+        }
+
+        #[test]
+        fn test_analyze() {
+            let blocks = analyze(&test_monad()).unwrap();
+            assert_eq!(blocks[2], MonadBlock { div_z: 26, add_x: -10, add_y: 4 });
+            let constraints = digit_constraints(&blocks).unwrap();
+            assert!(constraints.contains(&(0, 13, 3)));
+            assert!(constraints.contains(&(1, 2, -3)));
+            assert!(constraints.contains(&(10, 11, 0)));
+        }
+
+        #[test]
+        fn test_solve() {
+            let program = test_monad();
+            assert_eq!(solve(&program, true), Some(69687999499959));
+            assert_eq!(solve(&program, false), Some(14111326151114));
+
+            // nudging any single digit of the answer breaks its pair's constraint
+            let mut digits: Vec<isize> = vec![6, 9, 6, 8, 7, 9, 9, 9, 4, 9, 9, 9, 5, 9];
+            assert!(accepts(&program, &digits));
+            digits[4] -= 1;
+            assert!(!accepts(&program, &digits));
+        }
+    }
+}
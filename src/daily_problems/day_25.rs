@@ -0,0 +1,157 @@
+pub mod solutions {
+    use crate::AocBufReader;
+    use crate::utils::automaton::{Automaton, Simulation};
+    use crate::utils::grid::Grid;
+
+    /// Give up waiting for the sea cucumbers to stop after this many steps
+    const MAX_STEPS_TO_STOP: usize = 100_000;
+
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum SeaFloor {
+        Empty,
+        EastFacing,
+        SouthFacing,
+    }
+
+
+    impl SeaFloor {
+        fn from_char(c: char) -> SeaFloor {
+            match c {
+                '.' => SeaFloor::Empty,
+                '>' => SeaFloor::EastFacing,
+                'v' => SeaFloor::SouthFacing,
+                _ => panic!("unexpected sea floor char {}", c)
+            }
+        }
+
+        fn to_char(self) -> char {
+            match self {
+                SeaFloor::Empty => '.',
+                SeaFloor::EastFacing => '>',
+                SeaFloor::SouthFacing => 'v',
+            }
+        }
+    }
+
+
+    struct Herds {
+        grid: Grid<SeaFloor>,
+        // how many sea cucumbers moved on the latest step
+        n_moved: usize,
+    }
+
+
+    impl Herds {
+        fn from_lines(lines: Vec<String>) -> Herds {
+            Herds { grid: Grid::from_lines(lines, SeaFloor::from_char), n_moved: 0 }
+        }
+
+        /// Moves every cucumber in one herd at once: each first looks at the
+        /// space ahead of it (wrapping around the edge), then all that saw an
+        /// empty space step into it. Returns how many moved.
+        fn move_herd(&mut self, herd: SeaFloor, drow: isize, dcol: isize) -> usize {
+            let movers: Vec<((usize, usize), (usize, usize))> = self.grid.coordinates().into_iter()
+                .filter(|(row, col)| *self.grid.get(*row, *col) == herd)
+                .map(|(row, col)| ((row, col), self.grid.wrapping_offset(row, col, drow, dcol)))
+                .filter(|(_, (row, col))| *self.grid.get(*row, *col) == SeaFloor::Empty)
+                .collect();
+
+            for ((from_row, from_col), (to_row, to_col)) in movers.iter() {
+                self.grid.set(*from_row, *from_col, SeaFloor::Empty);
+                self.grid.set(*to_row, *to_col, herd);
+            }
+            movers.len()
+        }
+
+        /// The east-facing herd moves, then the south-facing herd; returns how many moved
+        fn step(&mut self) -> usize {
+            self.n_moved = self.move_herd(SeaFloor::EastFacing, 0, 1) + self.move_herd(SeaFloor::SouthFacing, 1, 0);
+            self.n_moved
+        }
+
+        /// Steps until nothing moves, returning the number of the first step
+        /// with no movement, or None if they are still moving after max_steps
+        fn steps_until_stopped(&mut self, max_steps: usize) -> Option<usize> {
+            Simulation::new(self).run_until(|herds| herds.n_moved == 0, max_steps)
+        }
+
+        fn render(&self) -> String {
+            self.grid.render(|cell| cell.to_char())
+        }
+
+        fn print(&self) {
+            println!("{}", self.render());
+        }
+    }
+
+
+    impl Automaton for Herds {
+        fn step(&mut self) {
+            Herds::step(self);
+        }
+    }
+
+
+    pub fn part_1(aoc_reader: AocBufReader) -> usize {
+        let stopped_step = Herds::from_lines(aoc_reader.collect()).steps_until_stopped(MAX_STEPS_TO_STOP);
+        stopped_step.expect("the sea cucumbers never stop moving")
+    }
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn lines(rows: &[&str]) -> Vec<String> {
+            rows.iter().map(|s| s.to_string()).collect()
+        }
+
+        #[test]
+        fn test_single_row() {
+            let mut herds = Herds::from_lines(lines(&["...>>>>>..."]));
+            assert_eq!(herds.step(), 1);
+            assert_eq!(herds.render(), "...>>>>.>..");
+            assert_eq!(herds.step(), 2);
+            assert_eq!(herds.render(), "...>>>.>.>.");
+        }
+
+        #[test]
+        fn test_wraparound_and_order() {
+            let mut herds = Herds::from_lines(lines(&[
+                "..........",
+                ".>v....v..",
+                ".......>..",
+                "..........",
+            ]));
+            herds.step();
+            assert_eq!(herds.render(), lines(&[
+                "..........",
+                ".>........",
+                "..v....v>.",
+                "..........",
+            ]).join("\n"));
+
+            let mut herds = Herds::from_lines(lines(&[">..", "...", "v.."]));
+            herds.step();
+            assert_eq!(herds.render(), lines(&["v>.", "...", "..."]).join("\n"));
+        }
+
+        #[test]
+        fn test_part_1() {
+            let rows = lines(&[
+                "v...>>.vv>",
+                ".vv>>.vv..",
+                ">>.>v>...v",
+                ">>v>>.>.v.",
+                "v>v.vv.v..",
+                ">.>>..v...",
+                ".vv..>.>v.",
+                "v.v..>>v.v",
+                "....v..v.>",
+            ]);
+            assert_eq!(Herds::from_lines(rows.clone()).steps_until_stopped(100), Some(58));
+            assert_eq!(Herds::from_lines(rows).steps_until_stopped(57), None);
+        }
+    }
+}
@@ -3,10 +3,9 @@ pub mod solutions {
     use std::collections::HashMap;
 
     use itertools::Zip;
-    use lazy_static::lazy_static;
-    use regex::Regex;
 
     use crate::input::read_input::AocBufReader;
+    use crate::utils::parse::scan;
 
     struct LineSegment {
         p1: (usize, usize),
@@ -74,22 +73,9 @@ pub mod solutions {
 
 
     fn read_input(aoc_reader: AocBufReader) -> Vec<LineSegment> {
-        lazy_static! {
-            static ref RE: Regex = Regex::new(r"^([0-9]*),([0-9]*) -> ([0-9]*),([0-9]*)$").unwrap();
-        }
-
         aoc_reader.map(|line|{
-            let cap = RE.captures(&line).unwrap();
-            LineSegment {
-                p1: (
-                    cap.get(1).unwrap().as_str().parse::<usize>().unwrap(),
-                    cap.get(2).unwrap().as_str().parse::<usize>().unwrap(),
-                ),
-                p2: (
-                    cap.get(3).unwrap().as_str().parse::<usize>().unwrap(),
-                    cap.get(4).unwrap().as_str().parse::<usize>().unwrap(),
-                ),
-            }
+            let (x1, y1, x2, y2) = scan::<(usize, usize, usize, usize)>("{},{} -> {},{}", &line).unwrap();
+            LineSegment { p1: (x1, y1), p2: (x2, y2) }
         }).collect::<Vec<LineSegment>>()
     }
 
@@ -1,118 +1,383 @@
 pub mod solutions {
-    use std::{collections::{HashMap, HashSet}, alloc::Layout};
+    use std::collections::HashMap;
 
     use crate::AocBufReader;
+    use crate::utils::automaton::Automaton;
+    use crate::utils::big_uint::BigUint;
 
-    struct LanternFish {
-        timer: usize,
-        is_baby: bool,
+    /// When a fish's timer is reset and what a newborn's starts at. With a
+    /// lifespan, fish are gone once they have lived that many days, and
+    /// don't reproduce on the day they die; fish in the input are newborn
+    /// on day 0 whatever their timer.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct ReproductionRules {
+        pub reset_timer: usize,
+        pub newborn_timer: usize,
+        pub lifespan: Option<usize>,
     }
 
 
-    impl LanternFish {
-        fn new(timer: usize) -> LanternFish {
-            LanternFish {timer: timer, is_baby: true}
+    pub const PUZZLE_RULES: ReproductionRules = ReproductionRules { reset_timer: 6, newborn_timer: 8, lifespan: None };
+
+
+    impl ReproductionRules {
+        /// Timers run from 0 up to the larger of the reset and newborn timers
+        fn n_timers(&self) -> usize {
+            self.reset_timer.max(self.newborn_timer) + 1
+        }
+
+        fn is_alive(&self, age: usize) -> bool {
+            self.lifespan.is_none_or(|lifespan| age < lifespan)
         }
     }
 
 
-    fn read_input_pt1(mut aoc_reader: AocBufReader) -> Vec<LanternFish> {
-        aoc_reader.next().unwrap().split(",").map(
-            |usize_str| usize_str.parse::<usize>().unwrap()
-        ).map(
-            |timer| LanternFish::new(timer)
-        ).collect()
+    struct LanternFishPopulation {
+        rules: ReproductionRules,
+        // counts by (timer, age); ages are all 0 when the rules have no lifespan
+        subpopulations: HashMap<(usize, usize), usize>
     }
 
 
-    fn run_simulation(mut lantern_fish: Vec<LanternFish>, n_days: usize) -> usize {
-        let mut n_new_fish: usize;
-        for _ in 0..n_days {
-            n_new_fish = 0;
-            for fish in lantern_fish.iter_mut() {
-                if fish.timer == 0 {
-                    n_new_fish += 1;
-                    fish.timer = 6;
-                    fish.is_baby = false;
+    impl LanternFishPopulation {
+        fn new(rules: ReproductionRules) -> LanternFishPopulation {
+            LanternFishPopulation { rules, subpopulations: HashMap::new() }
+        }
+
+        fn add_n_lantern_fishes(&mut self, timer: usize, n: usize) {
+            *self.subpopulations.entry((timer, 0)).or_insert(0) += n;
+        }
+
+        fn add_latern_fish(&mut self, timer: usize) {
+            self.add_n_lantern_fishes(timer, 1);
+        }
+
+        fn advance_simulation_one_day(&mut self) {
+            let mut next_subpopulations: HashMap<(usize, usize), usize> = HashMap::new();
+            for ((timer, age), n) in self.subpopulations.iter() {
+                if !self.rules.is_alive(age + 1) {
+                    continue
+                }
+                let next_age = if self.rules.lifespan.is_some() { age + 1 } else { 0 };
+                if *timer == 0 {
+                    // the zero population rolls over to the reset timer and has babies
+                    *next_subpopulations.entry((self.rules.reset_timer, next_age)).or_insert(0) += n;
+                    *next_subpopulations.entry((self.rules.newborn_timer, 0)).or_insert(0) += n;
                 } else {
-                    fish.timer -= 1;
+                    *next_subpopulations.entry((timer - 1, next_age)).or_insert(0) += n;
                 }
             }
-            for _ in 0..n_new_fish {
-                lantern_fish.push(LanternFish::new(8));
+            self.subpopulations = next_subpopulations;
+        }
+
+        fn total_population(&self) -> usize {
+            self.subpopulations.values().sum()
+        }
+    }
+
+
+    impl Automaton for LanternFishPopulation {
+        fn step(&mut self) {
+            self.advance_simulation_one_day();
+        }
+    }
+
+
+    /// table[timer] is how many fish a single fish starting with timer
+    /// becomes after some number of days, counting all its descendants
+    type GrowthTable = Vec<usize>;
+
+
+    /// Growth tables by rules and number of days, so that every input
+    /// counted under the same rules reuses one table
+    pub struct GrowthTables {
+        tables: HashMap<(ReproductionRules, usize), GrowthTable>,
+    }
+
+
+    impl GrowthTables {
+        pub fn new() -> GrowthTables {
+            GrowthTables { tables: HashMap::new() }
+        }
+
+        /// newborn[days] is how many fish a newborn becomes after that many days.
+        /// A fish with timer t gives birth after t + 1 days and every
+        /// reset_timer + 1 days after that, and each baby grows like any newborn.
+        fn compute(rules: ReproductionRules, n_days: usize) -> GrowthTable {
+            let descendants = |timer: usize, n_days: usize, newborn: &[usize]| -> usize {
+                let birth_days = (timer + 1..=n_days).step_by(rules.reset_timer + 1)
+                    .take_while(|birth_day| rules.is_alive(*birth_day));
+                rules.is_alive(n_days) as usize + birth_days.map(|birth_day| newborn[n_days - birth_day]).sum::<usize>()
+            };
+
+            let mut newborn: Vec<usize> = vec![];
+            for days in 0..=n_days {
+                let count = descendants(rules.newborn_timer, days, &newborn);
+                newborn.push(count);
             }
+            (0..rules.n_timers()).map(|timer| descendants(timer, n_days, &newborn)).collect()
+        }
+
+        fn get(&mut self, rules: ReproductionRules, n_days: usize) -> &GrowthTable {
+            self.tables.entry((rules, n_days)).or_insert_with(|| GrowthTables::compute(rules, n_days))
+        }
+
+        pub fn population_after(&mut self, rules: ReproductionRules, timers: &[usize], n_days: usize) -> usize {
+            let table = self.get(rules, n_days);
+            timers.iter().map(|timer| table[*timer]).sum()
         }
-        lantern_fish.len()
     }
 
-    pub fn part_1(aoc_reader: AocBufReader) -> usize {
-        let lantern_fish: Vec<LanternFish> = read_input_pt1(aoc_reader);
-        run_simulation(lantern_fish, 80)
+
+    /// The number system fish are counted in, which decides what happens
+    /// when populations get too big for a usize
+    trait Arithmetic {
+        type Count: Clone;
+
+        fn count(&self, n: usize) -> Self::Count;
+        fn add(&self, a: &Self::Count, b: &Self::Count) -> Self::Count;
+        fn mul(&self, a: &Self::Count, b: &Self::Count) -> Self::Count;
     }
 
-    struct LanternFishPopulation {
-        subpopulations: HashMap<usize, usize>
+
+    /// Plain usize counts, panicking rather than overflowing
+    struct Exact;
+
+    /// Counts modulo some number, which never overflow however many days pass
+    struct Modulo(u64);
+
+    /// Counts of any size; these grow by about 3.5 digits for every 100 days
+    struct Big;
+
+
+    impl Arithmetic for Exact {
+        type Count = usize;
+
+        fn count(&self, n: usize) -> usize {
+            n
+        }
+
+        fn add(&self, a: &usize, b: &usize) -> usize {
+            a.checked_add(*b).expect("population overflows a usize; count with Modulo or Big")
+        }
+
+        fn mul(&self, a: &usize, b: &usize) -> usize {
+            a.checked_mul(*b).expect("population overflows a usize; count with Modulo or Big")
+        }
     }
 
 
-    impl LanternFishPopulation {
-        fn new() -> LanternFishPopulation {
-            let mut sub_populations: HashMap<usize, usize> = HashMap::new();
-            for timer in 0..=8 {
-                sub_populations.insert(timer, 0);
-            }  
+    impl Arithmetic for Modulo {
+        type Count = u64;
 
-            LanternFishPopulation {subpopulations: sub_populations}
+        fn count(&self, n: usize) -> u64 {
+            n as u64 % self.0
         }
 
-        fn add_n_lantern_fishes(&mut self, timer: usize, n: usize) {
-            *self.subpopulations.entry(timer).or_insert(0) += n;
+        fn add(&self, a: &u64, b: &u64) -> u64 {
+            ((*a as u128 + *b as u128) % self.0 as u128) as u64
         }
 
-        fn add_latern_fish(&mut self, timer: usize) {
-            self.add_n_lantern_fishes(timer, 1);
+        fn mul(&self, a: &u64, b: &u64) -> u64 {
+            ((*a as u128 * *b as u128) % self.0 as u128) as u64
         }
+    }
 
-        fn advance_simulation_one_day(&mut self) {
-            let mut incoming_population_flux: usize = 0;
-            let mut outgoing_population_flux: usize;
-            for timer in (0usize..=8).rev() {
-                outgoing_population_flux = *self.subpopulations.get(&timer).unwrap();
-                self.subpopulations.insert(timer, incoming_population_flux);
-
-                // for the next population, the outgoing will become the incoming
-                incoming_population_flux = outgoing_population_flux;
-            }
 
-            // the zero population rolls over to 6
-            self.add_n_lantern_fishes(6, incoming_population_flux);
-            // the zero population has babies
-            self.add_n_lantern_fishes(8, incoming_population_flux);
+    impl Arithmetic for Big {
+        type Count = BigUint;
+
+        fn count(&self, n: usize) -> BigUint {
+            BigUint::from(n as u64)
         }
 
-        fn total_population(&self) -> usize {
-            self.subpopulations.values().sum()
+        fn add(&self, a: &BigUint, b: &BigUint) -> BigUint {
+            a + b
+        }
+
+        fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint {
+            a * b
         }
     }
 
 
-    fn read_input_pt2(mut aoc_reader: AocBufReader) -> LanternFishPopulation {
-        let timers: Vec<usize> =aoc_reader.next().unwrap().split(",").map(
-            |usize_str| usize_str.parse::<usize>().unwrap()
-        ).collect();
-        let mut lantern_fish_population = LanternFishPopulation::new();
-        for timer in timers {
-            lantern_fish_population.add_latern_fish(timer)
+    type Matrix<T> = Vec<Vec<T>>;
+
+
+    fn identity<A: Arithmetic>(arithmetic: &A, size: usize) -> Matrix<A::Count> {
+        (0..size).map(|row| (0..size).map(|col| arithmetic.count((row == col) as usize)).collect()).collect()
+    }
+
+
+    fn mat_mul<A: Arithmetic>(arithmetic: &A, a: &Matrix<A::Count>, b: &Matrix<A::Count>) -> Matrix<A::Count> {
+        (0..a.len()).map(|row| (0..b[0].len()).map(|col| {
+            (0..b.len()).fold(arithmetic.count(0), |sum, k| {
+                arithmetic.add(&sum, &arithmetic.mul(&a[row][k], &b[k][col]))
+            })
+        }).collect()).collect()
+    }
+
+
+    /// matrix to the power n_days, by repeated squaring
+    fn mat_pow<A: Arithmetic>(arithmetic: &A, matrix: &Matrix<A::Count>, mut n_days: u64) -> Matrix<A::Count> {
+        let mut result = identity(arithmetic, matrix.len());
+        let mut square = matrix.clone();
+        while n_days > 0 {
+            if n_days & 1 == 1 {
+                result = mat_mul(arithmetic, &result, &square);
+            }
+            square = mat_mul(arithmetic, &square, &square);
+            n_days >>= 1;
+        }
+        result
+    }
+
+
+    /// transition[new_timer][old_timer] is how many fish with new_timer one
+    /// fish with old_timer becomes after a day. Fish have no age here, so
+    /// the rules must not give them a lifespan.
+    fn transition_matrix<A: Arithmetic>(arithmetic: &A, rules: ReproductionRules) -> Matrix<A::Count> {
+        assert!(rules.lifespan.is_none(), "transition matrices can't track lifespans; use GrowthTables");
+        let n_timers = rules.n_timers();
+        let mut transition: Vec<Vec<usize>> = vec![vec![0; n_timers]; n_timers];
+        for timer in 1..n_timers {
+            transition[timer - 1][timer] = 1;
         }
-        lantern_fish_population
+        // every other timer just counts down; a fish at 0 becomes one fish
+        // at the reset timer and one at the newborn timer, which may coincide
+        transition[rules.reset_timer][0] += 1;
+        transition[rules.newborn_timer][0] += 1;
+        transition.iter().map(|row| row.iter().map(|n| arithmetic.count(*n)).collect()).collect()
+    }
+
+
+    /// How many fish there are after n_days, in O(log n_days) matrix products
+    fn population_after<A: Arithmetic>(
+        arithmetic: &A, rules: ReproductionRules, timers: &[usize], n_days: u64
+    ) -> A::Count {
+        let evolution = mat_pow(arithmetic, &transition_matrix(arithmetic, rules), n_days);
+        timers.iter().fold(arithmetic.count(0), |total, timer| {
+            evolution.iter().fold(total, |total, row| arithmetic.add(&total, &row[*timer]))
+        })
+    }
+
+
+    fn read_timers(mut aoc_reader: AocBufReader) -> Vec<usize> {
+        aoc_reader.next().unwrap().split(",").map(
+            |usize_str| usize_str.parse::<usize>().unwrap()
+        ).collect()
+    }
+
+
+    pub fn part_1(aoc_reader: AocBufReader) -> usize {
+        population_after(&Exact, PUZZLE_RULES, &read_timers(aoc_reader), 80)
     }
 
 
     pub fn part_2(aoc_reader: AocBufReader) -> usize {
-        let mut lantern_fish_population = read_input_pt2(aoc_reader);
-        for _ in 0..256 {
-            lantern_fish_population.advance_simulation_one_day();
+        population_after(&Exact, PUZZLE_RULES, &read_timers(aoc_reader), 256)
+    }
+
+
+    /// How many fish there are after n_days under any rules, lifespans
+    /// included. Passing the same growth_tables for several inputs counts
+    /// them all from one table per rules and day count.
+    pub fn population_with_rules(
+        aoc_reader: AocBufReader, rules: ReproductionRules, n_days: usize, growth_tables: &mut GrowthTables
+    ) -> usize {
+        growth_tables.population_after(rules, &read_timers(aoc_reader), n_days)
+    }
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::utils::automaton::Simulation;
+
+        const EXAMPLE: [usize; 5] = [3, 4, 3, 1, 2];
+
+        #[test]
+        fn test_population_after() {
+            assert_eq!(population_after(&Exact, PUZZLE_RULES, &EXAMPLE, 0), 5);
+            assert_eq!(population_after(&Exact, PUZZLE_RULES, &EXAMPLE, 18), 26);
+            assert_eq!(population_after(&Exact, PUZZLE_RULES, &EXAMPLE, 80), 5934);
+            assert_eq!(population_after(&Exact, PUZZLE_RULES, &EXAMPLE, 256), 26984457539);
+        }
+
+        fn simulate(rules: ReproductionRules, timers: &[usize], n_days: usize) -> Vec<usize> {
+            let mut population = LanternFishPopulation::new(rules);
+            for timer in timers.iter() {
+                population.add_latern_fish(*timer);
+            }
+            let mut totals = vec![population.total_population()];
+            Simulation::new(&mut population)
+                .observe(|_, population| totals.push(population.total_population()))
+                .run(n_days);
+            totals
+        }
+
+        #[test]
+        fn test_matches_day_by_day_simulation() {
+            for (n_days, total) in simulate(PUZZLE_RULES, &EXAMPLE, 100).into_iter().enumerate() {
+                assert_eq!(population_after(&Exact, PUZZLE_RULES, &EXAMPLE, n_days as u64), total);
+            }
+        }
+
+        #[test]
+        fn test_growth_tables() {
+            let mut growth_tables = GrowthTables::new();
+            assert_eq!(growth_tables.population_after(PUZZLE_RULES, &EXAMPLE, 18), 26);
+            assert_eq!(growth_tables.population_after(PUZZLE_RULES, &EXAMPLE, 256), 26984457539);
+            assert_eq!(growth_tables.population_after(PUZZLE_RULES, &[1, 1, 1], 256), 3 * growth_tables.get(PUZZLE_RULES, 256)[1]);
+            assert_eq!(growth_tables.tables.len(), 2);
+
+            let aoc_reader = AocBufReader::from_str("src/data/day_6_pt_1_test.txt");
+            assert_eq!(population_with_rules(aoc_reader, PUZZLE_RULES, 80, &mut growth_tables), 5934);
+            let rules = ReproductionRules { reset_timer: 6, newborn_timer: 8, lifespan: Some(5) };
+            let aoc_reader = AocBufReader::from_str("src/data/day_6_pt_1_test.txt");
+            assert_eq!(population_with_rules(aoc_reader, rules, 5, &mut growth_tables), 4);
+            assert_eq!(growth_tables.tables.len(), 4);
+        }
+
+        #[test]
+        fn test_custom_rules() {
+            let all_rules = vec![
+                ReproductionRules { reset_timer: 2, newborn_timer: 4, lifespan: None },
+                ReproductionRules { reset_timer: 8, newborn_timer: 3, lifespan: None },
+                ReproductionRules { reset_timer: 6, newborn_timer: 8, lifespan: Some(20) },
+                ReproductionRules { reset_timer: 4, newborn_timer: 4, lifespan: Some(9) },
+            ];
+            let mut growth_tables = GrowthTables::new();
+            for rules in all_rules {
+                for (n_days, total) in simulate(rules, &EXAMPLE, 60).into_iter().enumerate() {
+                    assert_eq!(growth_tables.population_after(rules, &EXAMPLE, n_days), total, "{:?} {}", rules, n_days);
+                    if rules.lifespan.is_none() {
+                        assert_eq!(population_after(&Exact, rules, &EXAMPLE, n_days as u64), total);
+                    }
+                }
+            }
+
+            // with a lifespan of 5 days no fish in the example lives to reproduce a second time
+            let rules = ReproductionRules { reset_timer: 6, newborn_timer: 8, lifespan: Some(5) };
+            assert_eq!(simulate(rules, &EXAMPLE, 5), vec![5, 5, 6, 7, 9, 4]);
+            assert_eq!(growth_tables.population_after(rules, &EXAMPLE, 5), 4);
+        }
+
+
+        #[test]
+        fn test_big_and_modular_counts() {
+            let exact = population_after(&Exact, PUZZLE_RULES, &EXAMPLE, 256) as u64;
+            assert_eq!(population_after(&Big, PUZZLE_RULES, &EXAMPLE, 256).to_u64(), Some(exact));
+            assert_eq!(population_after(&Modulo(1_000_000_007), PUZZLE_RULES, &EXAMPLE, 256), exact % 1_000_000_007);
+
+            let modulus: u64 = 998_244_353;
+            let big = population_after(&Big, PUZZLE_RULES, &EXAMPLE, 2000);
+            assert!(big.to_string().len() > 70);
+            assert_eq!(big.rem_small(modulus), population_after(&Modulo(modulus), PUZZLE_RULES, &EXAMPLE, 2000));
+
+            // 10^18 days is far too many to count exactly, but fine modulo a prime
+            let modular = population_after(&Modulo(modulus), PUZZLE_RULES, &EXAMPLE, 1_000_000_000_000_000_000);
+            assert!(modular < modulus);
         }
-        lantern_fish_population.total_population()
     }
-}
\ No newline at end of file
+}
@@ -1,11 +1,17 @@
 pub mod solutions {
-    use std::cmp::min;
-
     use crate::AocBufReader;
     use crate::utils::stats::f64_avg_usize;
 
 
-    fn required_fuel_pt1(positions: &Vec<usize>, candidate: usize) -> usize {
+    /// Where the crabs line up and the fuel it takes them to get there
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Alignment {
+        pub position: usize,
+        pub fuel: usize,
+    }
+
+
+    fn required_fuel_pt1(positions: &[usize], candidate: usize) -> usize {
         positions.iter().map(|pos| {
             match *pos >= candidate {
                 true => pos - candidate,
@@ -15,7 +21,7 @@ pub mod solutions {
     }
 
 
-    fn required_fuel_pt2(positions: &Vec<usize>, candidate: usize) -> usize {
+    fn required_fuel_pt2(positions: &[usize], candidate: usize) -> usize {
         positions.iter().map(|pos| {
             let diff: isize = *pos as isize - candidate as isize;
             if diff > 0 {
@@ -32,44 +38,154 @@ pub mod solutions {
     }
 
 
-    pub fn part_1(mut aoc_reader: AocBufReader) -> usize {
-        let positions: Vec<usize> = aoc_reader.next().unwrap()
-            .split(",").map(|x| x.parse::<usize>().unwrap()).collect();
+    /// The cheapest of candidates, preferring the lowest position on a tie
+    fn cheapest<I, F>(candidates: I, total_fuel: F) -> Alignment
+    where
+        I: IntoIterator<Item = usize>,
+        F: Fn(usize) -> usize,
+    {
+        candidates.into_iter()
+            .map(|position| Alignment { position, fuel: total_fuel(position) })
+            .min_by_key(|alignment| (alignment.fuel, alignment.position))
+            .unwrap()
+    }
+
 
-        let min_position = *positions.iter().min().unwrap();
+    /// With fuel equal to distance, any median is optimal: moving away from
+    /// it brings at least as many crabs further as it brings closer
+    fn align_linear(positions: &[usize]) -> Alignment {
+        let mut sorted = positions.to_vec();
+        sorted.sort_unstable();
+        let median = sorted[(sorted.len() - 1) / 2];
+        Alignment { position: median, fuel: required_fuel_pt1(positions, median) }
+    }
+
+
+    /// With triangular fuel the total is (sum of d^2 + sum of |d|) / 2, which
+    /// is minimized within half a step of the mean, so only the positions
+    /// around it need checking
+    fn align_triangular(positions: &[usize]) -> Alignment {
+        let mean = f64_avg_usize(positions);
         let max_position = *positions.iter().max().unwrap();
+        let lowest = (mean - 0.5).floor().max(0.0) as usize;
+        let highest = ((mean + 0.5).ceil() as usize).min(max_position);
+        cheapest(lowest..=highest, |position| required_fuel_pt2(positions, position))
+    }
+
 
-        let mut min_cost: Option<usize> = None;
-        let mut cost: usize;
-        for position in  min_position..=max_position {
-            cost = required_fuel_pt1(&positions, position);
-            match min_cost {
-                Some(prior_min) => if cost <= prior_min {min_cost = Some(cost)},
-                None => min_cost = Some(cost)
+    /// Ternary search for the best position, given crab_fuel(distance) is the
+    /// fuel one crab burns to move that far. This finds the optimum whenever
+    /// the total fuel is convex in the position, as it is for any crab_fuel
+    /// that is convex and non-decreasing in the distance.
+    fn align_convex<F: Fn(usize) -> usize>(positions: &[usize], crab_fuel: F) -> Alignment {
+        let total_fuel = |candidate: usize| -> usize {
+            positions.iter().map(|pos| crab_fuel(pos.abs_diff(candidate))).sum()
+        };
+
+        let mut lo = *positions.iter().min().unwrap();
+        let mut hi = *positions.iter().max().unwrap();
+        while hi - lo > 2 {
+            let third = (hi - lo) / 3;
+            let (m1, m2) = (lo + third, hi - third);
+            let (fuel_1, fuel_2) = (total_fuel(m1), total_fuel(m2));
+            if fuel_1 < fuel_2 {
+                hi = m2 - 1;
+            } else if fuel_1 > fuel_2 {
+                lo = m1 + 1;
+            } else {
+                // convexity puts a minimum between equal points
+                lo = m1;
+                hi = m2;
             }
         }
+        cheapest(lo..=hi, total_fuel)
+    }
+
+
+    fn read_positions(mut aoc_reader: AocBufReader) -> Vec<usize> {
+        aoc_reader.next().unwrap()
+            .split(",").map(|x| x.parse::<usize>().unwrap()).collect()
+    }
+
 
-        min_cost.unwrap()
+    /// The cheapest alignment when crab_fuel(distance) is the fuel one crab
+    /// burns to move that far, for any convex, non-decreasing crab_fuel
+    pub fn align_with_cost<F: Fn(usize) -> usize>(aoc_reader: AocBufReader, crab_fuel: F) -> Alignment {
+        align_convex(&read_positions(aoc_reader), crab_fuel)
     }
 
 
-    pub fn part_2(mut aoc_reader: AocBufReader) -> usize {
-        let positions: Vec<usize> = aoc_reader.next().unwrap()
-            .split(",").map(|x| x.parse::<usize>().unwrap()).collect();
+    pub fn align_part_1(aoc_reader: AocBufReader) -> Alignment {
+        align_linear(&read_positions(aoc_reader))
+    }
 
-        let min_position = *positions.iter().min().unwrap();
-        let max_position = *positions.iter().max().unwrap();
 
-        let mut min_cost: Option<usize> = None;
-        let mut cost: usize;
-        for position in  min_position..=max_position {
-            cost = required_fuel_pt2(&positions, position);
-            match min_cost {
-                Some(prior_min) => if cost <= prior_min {min_cost = Some(cost)},
-                None => min_cost = Some(cost)
-            }
+    pub fn part_1(aoc_reader: AocBufReader) -> usize {
+        align_part_1(aoc_reader).fuel
+    }
+
+
+    pub fn align_part_2(aoc_reader: AocBufReader) -> Alignment {
+        align_triangular(&read_positions(aoc_reader))
+    }
+
+
+    pub fn part_2(aoc_reader: AocBufReader) -> usize {
+        align_part_2(aoc_reader).fuel
+    }
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::utils::random::Lcg;
+
+        fn example() -> Vec<usize> {
+            vec![16, 1, 2, 0, 4, 2, 7, 1, 2, 14]
         }
 
-        min_cost.unwrap()
+        /// Tries every position from the lowest crab to the highest
+        fn brute_force<F: Fn(usize) -> usize>(positions: &[usize], total_fuel: F) -> Alignment {
+            let min_position = *positions.iter().min().unwrap();
+            let max_position = *positions.iter().max().unwrap();
+            cheapest(min_position..=max_position, total_fuel)
+        }
+
+        #[test]
+        fn test_example() {
+            assert_eq!(align_linear(&example()), Alignment { position: 2, fuel: 37 });
+            assert_eq!(align_triangular(&example()), Alignment { position: 5, fuel: 168 });
+            assert_eq!(align_convex(&example(), |d| d), Alignment { position: 2, fuel: 37 });
+            assert_eq!(align_convex(&example(), |d| d * (d + 1) / 2), Alignment { position: 5, fuel: 168 });
+        }
+
+        #[test]
+        fn test_entry_points() {
+            let reader = || AocBufReader::from_str("src/data/day_7_pt_1_test.txt");
+            assert_eq!(align_part_1(reader()), Alignment { position: 2, fuel: 37 });
+            assert_eq!(align_part_2(reader()), Alignment { position: 5, fuel: 168 });
+            assert_eq!(align_with_cost(reader(), |d| d * (d + 1) / 2), align_part_2(reader()));
+            assert_eq!(align_with_cost(reader(), |d| d * d), Alignment { position: 5, fuel: 291 });
+        }
+
+        #[test]
+        fn test_against_brute_force() {
+            let mut rng = Lcg::new(7);
+            for _ in 0..100 {
+                let n_crabs = rng.next_in(1, 30) as usize;
+                let positions: Vec<usize> = (0..n_crabs).map(|_| rng.next_in(0, 200) as usize).collect();
+
+                let linear = |candidate| required_fuel_pt1(&positions, candidate);
+                let triangular = |candidate| required_fuel_pt2(&positions, candidate);
+                assert_eq!(align_linear(&positions).fuel, brute_force(&positions, linear).fuel);
+                assert_eq!(align_triangular(&positions), brute_force(&positions, triangular));
+
+                let cubic = |d: usize| d * d * d;
+                let expected = brute_force(&positions, |candidate| {
+                    positions.iter().map(|pos| cubic(pos.abs_diff(candidate))).sum()
+                });
+                assert_eq!(align_convex(&positions, cubic).fuel, expected.fuel, "{:?}", positions);
+            }
+        }
     }
-}
\ No newline at end of file
+}
@@ -1,5 +1,7 @@
 pub mod solutions {
-    use std::collections::{HashMap, HashSet};
+    use std::collections::HashSet;
+    use std::fmt;
+
     use lazy_static::lazy_static;
 
     use crate::AocBufReader;
@@ -21,122 +23,245 @@ pub mod solutions {
     }
 
 
-    struct WireMapping {
-        str_to_val: HashMap<String, usize>,
-        val_to_chars: HashMap<usize, HashSet<char>>
+    const N_SEGMENTS: usize = 7;
+    const ALL_SEGMENTS: u8 = (1 << N_SEGMENTS) - 1;
+
+    /// The segments each digit lights on a correctly wired display
+    const DIGIT_SEGMENTS: [&str; 10] = [
+        "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+    ];
+
+    lazy_static! {
+        static ref DIGIT_MASKS: Vec<u8> = DIGIT_SEGMENTS.iter().map(|digit| to_mask(digit).unwrap()).collect();
     }
 
 
-    impl WireMapping {
-        fn new() -> WireMapping {
-            WireMapping {
-                str_to_val: HashMap::new(),
-                val_to_chars: HashMap::new()
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum DecodeError {
+        /// Empty, or has a repeated wire or one outside a to g
+        InvalidPattern { pattern: String },
+        /// No wiring makes every pattern a digit
+        NoConsistentWiring,
+        /// The pattern isn't a digit under any wiring that fits the patterns
+        UnknownDigit { pattern: String },
+        /// The wirings that fit the patterns disagree on what this pattern shows
+        AmbiguousDigit { pattern: String, candidates: Vec<usize> },
+    }
+
+
+    impl fmt::Display for DecodeError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                DecodeError::InvalidPattern { pattern } => write!(f, "invalid pattern {:?}", pattern),
+                DecodeError::NoConsistentWiring => write!(f, "no wiring turns every pattern into a digit"),
+                DecodeError::UnknownDigit { pattern } => write!(f, "{} is not a digit", pattern),
+                DecodeError::AmbiguousDigit { pattern, candidates } => {
+                    write!(f, "{} could be any of {:?}", pattern, candidates)
+                },
             }
         }
+    }
 
-        fn get_patterns_of_len(patterns: &[String], n: usize) -> Vec<&String> {
-            patterns.iter().filter(|pattern| pattern.len() == n).collect()
+
+    /// The wires (or segments) in pattern as bits, a in the lowest
+    fn to_mask(pattern: &str) -> Result<u8, DecodeError> {
+        let invalid = || DecodeError::InvalidPattern { pattern: pattern.to_string() };
+        let mut mask: u8 = 0;
+        for c in pattern.chars() {
+            if !('a'..='g').contains(&c) {
+                return Err(invalid())
+            }
+            let bit = 1 << (c as u8 - b'a');
+            if mask & bit != 0 {
+                return Err(invalid())
+            }
+            mask |= bit;
         }
+        match mask {
+            0 => Err(invalid()),
+            _ => Ok(mask)
+        }
+    }
 
 
-        fn get_pattern_of_len(patterns: &[String], n: usize) -> &String {
-            let patterns = WireMapping::get_patterns_of_len(&patterns[..], n);
-            assert_eq!(patterns.len(), 1);
+    /// segment_of_wire[wire] is the segment that wire lights
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct WireMapping {
+        segment_of_wire: [usize; N_SEGMENTS],
+    }
 
-            patterns[0]
-        }
 
+    impl WireMapping {
+        fn translate(&self, wires: u8) -> u8 {
+            (0..N_SEGMENTS).filter(|wire| wires & (1 << wire) != 0)
+                .fold(0, |segments, wire| segments | (1 << self.segment_of_wire[wire]))
+        }
 
-        fn add_entry(&mut self, str: &String, val: usize) {
-            self.str_to_val.insert(str.to_owned(), val);
-            self.val_to_chars.insert(val, str.chars().collect::<HashSet<char>>());
+        fn digit_for(&self, wires: u8) -> Option<usize> {
+            let segments = self.translate(wires);
+            DIGIT_MASKS.iter().position(|digit| *digit == segments)
         }
+    }
 
 
-        fn build(&mut self, patterns: Vec<String>) {
-            let one_str: &String = WireMapping::get_pattern_of_len(&patterns, 2);
-            self.add_entry(one_str, 1usize);
+    /// Which segments each wire could light given the pattern lengths alone.
+    /// A pattern can only show a digit with as many segments, so its wires
+    /// must light segments of such a digit and the other wires segments
+    /// outside one.
+    fn candidate_segments(patterns: &[u8]) -> [u8; N_SEGMENTS] {
+        let mut candidates = [ALL_SEGMENTS; N_SEGMENTS];
+        for pattern in patterns {
+            let same_len: Vec<u8> = DIGIT_MASKS.iter().copied()
+                .filter(|digit| digit.count_ones() == pattern.count_ones())
+                .collect();
+            let inside = same_len.iter().fold(0, |segments, digit| segments | digit);
+            let outside = same_len.iter().fold(0, |segments, digit| segments | (!digit & ALL_SEGMENTS));
+            for (wire, wire_candidates) in candidates.iter_mut().enumerate() {
+                *wire_candidates &= if pattern & (1 << wire) != 0 { inside } else { outside };
+            }
+        }
+        candidates
+    }
 
-            let seven_str: &String = WireMapping::get_pattern_of_len(&patterns, 3);
-            self.add_entry(seven_str, 7usize);
 
-            let four_str: &String = WireMapping::get_pattern_of_len(&patterns, 4);
-            self.add_entry(four_str, 4usize);
+    /// Tries every assignment of the remaining wires to unused candidate
+    /// segments, keeping the full wirings that turn every pattern into a digit
+    fn search_wirings(
+        wire: usize,
+        used_segments: u8,
+        segment_of_wire: &mut [usize; N_SEGMENTS],
+        candidates: &[u8; N_SEGMENTS],
+        patterns: &[u8],
+        found: &mut Vec<WireMapping>,
+    ) {
+        if wire == N_SEGMENTS {
+            let mapping = WireMapping { segment_of_wire: *segment_of_wire };
+            if patterns.iter().all(|pattern| mapping.digit_for(*pattern).is_some()) {
+                found.push(mapping);
+            }
+            return
+        }
+        for segment in 0..N_SEGMENTS {
+            let bit = 1 << segment;
+            if candidates[wire] & bit != 0 && used_segments & bit == 0 {
+                segment_of_wire[wire] = segment;
+                search_wirings(wire + 1, used_segments | bit, segment_of_wire, candidates, patterns, found);
+            }
+        }
+    }
 
-            let eight_str: &String = WireMapping::get_pattern_of_len(&patterns, 7);
-            self.add_entry(eight_str, 8usize);
 
-            let strs_of_len_5: Vec<&String> = WireMapping::get_patterns_of_len(&patterns, 5);
-            let strs_of_len_6: Vec<&String> = WireMapping::get_patterns_of_len(&patterns, 6);
-            for str in strs_of_len_5.iter() {
-                let str_chars: HashSet<char> = str.chars().collect();
-                if self.val_to_chars.get(&1).unwrap().is_subset(&str_chars) {
-                    self.add_entry(str, 3usize);
-                }
-            }
+    /// Every wiring that fits the patterns seen; any subset of the ten
+    /// digits' patterns can be given
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Solution {
+        Unique(WireMapping),
+        Ambiguous(Vec<WireMapping>),
+    }
 
-            for str in strs_of_len_6.iter() {
-                let str_chars: HashSet<char> = str.chars().collect();
-                if !self.val_to_chars.get(&1).unwrap().is_subset(&str_chars) {
-                    self.add_entry(str, 6usize);
-                }
-            }
 
-            for str in strs_of_len_5.iter() {
-                let str_chars: HashSet<char> = str.chars().collect();
-                if str_chars == *self.val_to_chars.get(&3).unwrap() {
-                    continue
-                } else if str_chars.is_subset(self.val_to_chars.get(&6).unwrap()) {
-                    self.add_entry(str, 5usize);
-                } else {
-                    self.add_entry(str, 2usize);
-                }
+    impl Solution {
+        fn solve(patterns: &[String]) -> Result<Solution, DecodeError> {
+            let masks = patterns.iter().map(|pattern| to_mask(pattern)).collect::<Result<Vec<u8>, DecodeError>>()?;
+            let mut found: Vec<WireMapping> = vec![];
+            search_wirings(0, 0, &mut [0; N_SEGMENTS], &candidate_segments(&masks), &masks, &mut found);
+            match found.len() {
+                0 => Err(DecodeError::NoConsistentWiring),
+                1 => Ok(Solution::Unique(found.pop().unwrap())),
+                _ => Ok(Solution::Ambiguous(found)),
             }
+        }
 
-            for str in strs_of_len_6.iter() {
-                let str_chars: HashSet<char> = str.chars().collect();
-                if str_chars == *self.val_to_chars.get(&6).unwrap() {
-                    continue
-                } else if self.val_to_chars.get(&5).unwrap().is_subset(&str_chars) {
-                    self.add_entry(str, 9usize);
-                } else {
-                    self.add_entry(str, 0usize);
-                }
+        fn mappings(&self) -> &[WireMapping] {
+            match self {
+                Solution::Unique(mapping) => std::slice::from_ref(mapping),
+                Solution::Ambiguous(mappings) => mappings,
             }
         }
 
-        fn get_num_for_digit(&self, digit: &String) -> usize {
-            let digit_chars: HashSet<char> = digit.chars().collect();
-
-            for val in 0..10 {
-                if *self.val_to_chars.get(&val).unwrap() == digit_chars {
-                    return val
-                }
+        /// The digit pattern shows, provided every fitting wiring agrees on it
+        fn digit_for(&self, pattern: &str) -> Result<usize, DecodeError> {
+            let wires = to_mask(pattern)?;
+            let mut candidates: Vec<usize> = self.mappings().iter()
+                .filter_map(|mapping| mapping.digit_for(wires))
+                .collect();
+            candidates.sort_unstable();
+            candidates.dedup();
+            match candidates.len() {
+                0 => Err(DecodeError::UnknownDigit { pattern: pattern.to_string() }),
+                1 => Ok(candidates[0]),
+                _ => Err(DecodeError::AmbiguousDigit { pattern: pattern.to_string(), candidates }),
             }
-            panic!("Unmapped digit {}", digit);
         }
 
-        fn decode(&self, digits: Vec<String>) -> usize {
-            digits.iter().rev().enumerate().map(|(place, digit)| {
-                self.get_num_for_digit(digit) * 10usize.pow(place as u32)
-            }).sum()
+        fn decode(&self, digits: &[String]) -> Result<usize, DecodeError> {
+            digits.iter().try_fold(0, |val, digit| Ok(10 * val + self.digit_for(digit)?))
         }
     }
 
 
+    fn decode_line(line: &str) -> Result<usize, DecodeError> {
+        let input_output: Vec<&str> = line.split("|").collect();
+        let inputs: Vec<String> = input_output[0].split_whitespace().map(|s| s.to_string()).collect();
+        let digits: Vec<String> = input_output[1].split_whitespace().map(|s| s.to_string()).collect();
+        Solution::solve(&inputs)?.decode(&digits)
+    }
+
+
     pub fn part_2(aoc_reader: AocBufReader) -> usize {
-        let mut sum: usize = 0;
-        for line in aoc_reader {
-            let input_output: Vec<&str> = line.split("|").collect();
-            let inputs: Vec<String> = input_output[0].split_whitespace().map(|s| s.to_string()).collect();
-            let digits: Vec<String> = input_output[1].split_whitespace().map(|s| s.to_string()).collect();
+        aoc_reader.map(|line| {
+            decode_line(&line).unwrap_or_else(|err| panic!("could not decode {}: {}", line, err))
+        }).sum()
+    }
 
-            let mut wire_map = WireMapping::new();
-            wire_map.build(inputs);
-            sum += wire_map.decode(digits);
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn strings(patterns: &str) -> Vec<String> {
+            patterns.split_whitespace().map(|s| s.to_string()).collect()
+        }
+
+        #[test]
+        fn test_decode_line() {
+            let line = "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf";
+            assert_eq!(decode_line(line), Ok(5353));
+
+            let solution = Solution::solve(&strings(line.split("|").next().unwrap())).unwrap();
+            match solution {
+                Solution::Unique(mapping) => assert_eq!(mapping.segment_of_wire, [2, 5, 6, 0, 1, 3, 4]),
+                Solution::Ambiguous(_) => panic!("the full set of patterns should fix the wiring"),
+            }
         }
 
-        sum
+        #[test]
+        fn test_partial_patterns() {
+            let solution = Solution::solve(&strings("ab dab eafb")).unwrap();
+            assert!(matches!(solution, Solution::Ambiguous(_)));
+            assert_eq!(solution.mappings().len(), 8);
+            assert_eq!(solution.decode(&strings("ab eafb dab")), Ok(147));
+            assert_eq!(solution.digit_for("acedgfb"), Ok(8));
+
+            // knowing only which wires light 1, a pattern with one of them could be a 2 or a 5
+            let solution = Solution::solve(&strings("ab")).unwrap();
+            assert_eq!(
+                solution.digit_for("acdef"),
+                Err(DecodeError::AmbiguousDigit { pattern: "acdef".to_string(), candidates: vec![2, 5] })
+            );
+
+            assert_eq!(Solution::solve(&[]).unwrap().mappings().len(), 5040);
+        }
+
+        #[test]
+        fn test_errors() {
+            assert_eq!(Solution::solve(&strings("ab cd")), Err(DecodeError::NoConsistentWiring));
+            assert_eq!(Solution::solve(&strings("a")), Err(DecodeError::NoConsistentWiring));
+            assert_eq!(Solution::solve(&strings("ab abz")), Err(DecodeError::InvalidPattern { pattern: "abz".to_string() }));
+            assert_eq!(Solution::solve(&strings("abca")), Err(DecodeError::InvalidPattern { pattern: "abca".to_string() }));
+
+            let solution = Solution::solve(&strings("ab dab eafb")).unwrap();
+            assert_eq!(solution.digit_for("a"), Err(DecodeError::UnknownDigit { pattern: "a".to_string() }));
+        }
     }
 }
@@ -3,11 +3,11 @@ mod input;
 mod utils;
 
 pub use input::read_input::AocBufReader;
-pub use daily_problems::day_22::solutions::{part_1};
+pub use daily_problems::day_22::solutions::{part_2};
 
 
 fn main() {
     let aoc_reader = AocBufReader::from_str("src/data/day_22_pt_2.txt");
-    let result: usize = part_1(aoc_reader);
+    let result: usize = part_2(aoc_reader);
     println!("{}", result);
 }
\ No newline at end of file
@@ -1,6 +1,16 @@
 use lazy_static::lazy_static;
 use regex::Regex;
 
+pub mod automaton;
+pub mod big_uint;
+pub mod grid;
+pub mod interval;
+pub mod netpbm;
+pub mod ocr;
+pub mod parse;
+#[cfg(test)]
+pub mod random;
+
 lazy_static! {
     static ref LOWER_CASE_RE: Regex = Regex::new(r"^[a-z]*$").unwrap();
 }
@@ -54,7 +64,7 @@ pub mod str_utils {
 
 
 pub mod stats {
-    pub fn f64_avg_usize(vals: &Vec<usize>) -> f64 {
+    pub fn f64_avg_usize(vals: &[usize]) -> f64 {
         vals.iter().sum::<usize>() as f64 / vals.len() as f64
     }
 }
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+
+/// Anything that evolves one discrete step at a time
+pub trait Automaton {
+    fn step(&mut self);
+}
+
+
+/// The states after steps start and start + period are the same, and so on forever
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub period: usize,
+}
+
+
+/// Called with the step number and the state after that step
+type Observer<'a, A> = Box<dyn FnMut(usize, &A) + 'a>;
+
+
+/// Drives an automaton, counting steps and telling observers about each new state
+pub struct Simulation<'a, A: Automaton> {
+    state: &'a mut A,
+    n_steps: usize,
+    observers: Vec<Observer<'a, A>>,
+}
+
+
+impl<'a, A: Automaton> Simulation<'a, A> {
+    pub fn new(state: &'a mut A) -> Simulation<'a, A> {
+        Simulation { state, n_steps: 0, observers: vec![] }
+    }
+
+    /// Calls observer after every step
+    pub fn observe<F: FnMut(usize, &A) + 'a>(mut self, observer: F) -> Simulation<'a, A> {
+        self.observers.push(Box::new(observer));
+        self
+    }
+
+    pub fn state(&self) -> &A {
+        self.state
+    }
+
+    /// How many steps have run so far
+    pub fn n_steps(&self) -> usize {
+        self.n_steps
+    }
+
+    pub fn step(&mut self) {
+        self.state.step();
+        self.n_steps += 1;
+        for observer in self.observers.iter_mut() {
+            observer(self.n_steps, self.state);
+        }
+    }
+
+    pub fn run(&mut self, n_steps: usize) {
+        for _ in 0..n_steps {
+            self.step();
+        }
+    }
+
+    /// Steps until predicate holds, returning that step's number, or None if
+    /// it still doesn't after max_steps more steps
+    pub fn run_until<P: Fn(&A) -> bool>(&mut self, predicate: P, max_steps: usize) -> Option<usize> {
+        for _ in 0..max_steps {
+            self.step();
+            if predicate(self.state) {
+                return Some(self.n_steps)
+            }
+        }
+        None
+    }
+}
+
+
+impl<'a, A: Automaton + Clone + Eq + Hash> Simulation<'a, A> {
+    /// Steps until some state comes round again, or None if none does
+    /// within max_steps more steps. Every state seen is kept, so this suits
+    /// automata that settle into a cycle quickly.
+    pub fn find_cycle(&mut self, max_steps: usize) -> Option<Cycle> {
+        let mut seen: HashMap<A, usize> = HashMap::new();
+        seen.insert(self.state.clone(), self.n_steps);
+        for _ in 0..max_steps {
+            self.step();
+            if let Some(start) = seen.get(self.state) {
+                return Some(Cycle { start: *start, period: self.n_steps - start })
+            }
+            seen.insert(self.state.clone(), self.n_steps);
+        }
+        None
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts up by one, wrapping from limit back to reset
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    struct Counter {
+        val: usize,
+        limit: usize,
+        reset: usize,
+    }
+
+    impl Automaton for Counter {
+        fn step(&mut self) {
+            self.val = if self.val == self.limit { self.reset } else { self.val + 1 };
+        }
+    }
+
+    #[test]
+    fn test_run_until() {
+        let mut counter = Counter { val: 0, limit: 9, reset: 0 };
+        let mut simulation = Simulation::new(&mut counter);
+        assert_eq!(simulation.run_until(|c| c.val == 7, 100), Some(7));
+        assert_eq!(simulation.run_until(|c| c.val == 7, 5), None);
+        assert_eq!(simulation.n_steps(), 12);
+        assert_eq!(simulation.state().val, 2);
+        simulation.run(3);
+        assert_eq!(simulation.state().val, 5);
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        let mut counter = Counter { val: 0, limit: 9, reset: 4 };
+        assert_eq!(Simulation::new(&mut counter).find_cycle(100), Some(Cycle { start: 4, period: 6 }));
+
+        let mut counter = Counter { val: 0, limit: 9, reset: 4 };
+        assert_eq!(Simulation::new(&mut counter).find_cycle(5), None);
+    }
+
+    #[test]
+    fn test_observers() {
+        let mut counter = Counter { val: 0, limit: 2, reset: 0 };
+        let mut history: Vec<(usize, usize)> = vec![];
+        let mut n_calls: usize = 0;
+        {
+            let mut simulation = Simulation::new(&mut counter)
+                .observe(|step, c| history.push((step, c.val)))
+                .observe(|_, _| n_calls += 1);
+            simulation.run(4);
+        }
+        assert_eq!(history, vec![(1, 1), (2, 2), (3, 0), (4, 1)]);
+        assert_eq!(n_calls, 4);
+    }
+}
@@ -0,0 +1,152 @@
+use std::fmt;
+use std::ops::{Add, Mul};
+
+
+/// Each limb holds a value below LIMB_BASE
+const LIMB_BITS: u32 = 32;
+const LIMB_BASE: u64 = 1 << LIMB_BITS;
+/// Display peels off this many decimal digits at a time
+const DECIMAL_CHUNK: u64 = 1_000_000_000;
+
+
+/// An arbitrarily large unsigned integer, stored as base 2^32 limbs with the
+/// least significant first and no trailing zero limbs
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+
+impl BigUint {
+    pub fn zero() -> BigUint {
+        BigUint { limbs: vec![] }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    fn from_limbs(mut limbs: Vec<u32>) -> BigUint {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        BigUint { limbs }
+    }
+
+    /// Divides in place by a single limb, returning the remainder
+    fn div_rem_small(&mut self, divisor: u64) -> u64 {
+        let mut remainder: u64 = 0;
+        for limb in self.limbs.iter_mut().rev() {
+            let current = (remainder << LIMB_BITS) | *limb as u64;
+            *limb = (current / divisor) as u32;
+            remainder = current % divisor;
+        }
+        *self = BigUint::from_limbs(std::mem::take(&mut self.limbs));
+        remainder
+    }
+
+    /// The remainder on dividing by divisor, which must fit in a limb
+    pub fn rem_small(&self, divisor: u64) -> u64 {
+        assert!(divisor > 0 && divisor <= LIMB_BASE, "divisor {} does not fit in a limb", divisor);
+        self.clone().div_rem_small(divisor)
+    }
+
+    /// The value, if it fits in a u64
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.limbs.len() {
+            0 => Some(0),
+            1 => Some(self.limbs[0] as u64),
+            2 => Some(self.limbs[0] as u64 | (self.limbs[1] as u64) << LIMB_BITS),
+            _ => None
+        }
+    }
+}
+
+
+impl From<u64> for BigUint {
+    fn from(val: u64) -> BigUint {
+        BigUint::from_limbs(vec![val as u32, (val >> LIMB_BITS) as u32])
+    }
+}
+
+
+impl Add for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: &BigUint) -> BigUint {
+        let mut limbs: Vec<u32> = vec![];
+        let mut carry: u64 = 0;
+        for idx in 0..self.limbs.len().max(other.limbs.len()) {
+            let sum = carry
+                + *self.limbs.get(idx).unwrap_or(&0) as u64
+                + *other.limbs.get(idx).unwrap_or(&0) as u64;
+            limbs.push((sum % LIMB_BASE) as u32);
+            carry = sum / LIMB_BASE;
+        }
+        limbs.push(carry as u32);
+        BigUint::from_limbs(limbs)
+    }
+}
+
+
+impl Mul for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: &BigUint) -> BigUint {
+        let mut limbs: Vec<u32> = vec![0; self.limbs.len() + other.limbs.len()];
+        for (i, a) in self.limbs.iter().enumerate() {
+            let mut carry: u64 = 0;
+            for (j, b) in other.limbs.iter().enumerate() {
+                let product = *a as u64 * *b as u64 + limbs[i + j] as u64 + carry;
+                limbs[i + j] = (product % LIMB_BASE) as u32;
+                carry = product / LIMB_BASE;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        BigUint::from_limbs(limbs)
+    }
+}
+
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0")
+        }
+        let mut remaining = self.clone();
+        let mut chunks: Vec<u64> = vec![];
+        while !remaining.is_zero() {
+            chunks.push(remaining.div_rem_small(DECIMAL_CHUNK));
+        }
+        write!(f, "{}", chunks.pop().unwrap())?;
+        for chunk in chunks.iter().rev() {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic() {
+        let a = BigUint::from(u64::MAX);
+        let b = BigUint::from(1);
+        assert_eq!((&a + &b).to_string(), "18446744073709551616");
+        assert_eq!((&a * &a).to_string(), "340282366920938463426481119284349108225");
+        assert_eq!((&a * &BigUint::zero()), BigUint::zero());
+        assert_eq!((&BigUint::from(12345) * &BigUint::from(6789)).to_u64(), Some(83810205));
+        assert_eq!((&a + &a).to_u64(), None);
+        assert_eq!(a.rem_small(10), 5);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(BigUint::zero().to_string(), "0");
+        assert_eq!(BigUint::from(1_000_000_000).to_string(), "1000000000");
+        assert_eq!(BigUint::from(1_000_000_007_000_000_001).to_string(), "1000000007000000001");
+    }
+}
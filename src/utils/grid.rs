@@ -0,0 +1,107 @@
+/// A rectangular grid addressed as (row, column) from the top left
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    cells: Vec<Vec<T>>,
+}
+
+
+impl<T: Clone> Grid<T> {
+    pub fn new(n_rows: usize, n_cols: usize, fill: T) -> Grid<T> {
+        Grid { cells: vec![vec![fill; n_cols]; n_rows] }
+    }
+
+    pub fn from_rows(cells: Vec<Vec<T>>) -> Grid<T> {
+        assert!(cells.iter().all(|row| row.len() == cells[0].len()), "grid rows must all be the same length");
+        Grid { cells }
+    }
+
+    /// Builds a grid from lines of text, converting each character with to_cell
+    pub fn from_lines<I, F>(lines: I, to_cell: F) -> Grid<T>
+    where
+        I: IntoIterator<Item = String>,
+        F: Fn(char) -> T,
+    {
+        Grid::from_rows(lines.into_iter().map(|line| line.chars().map(&to_cell).collect()).collect())
+    }
+
+    pub fn n_rows(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn n_cols(&self) -> usize {
+        match self.cells.first() {
+            Some(row) => row.len(),
+            None => 0
+        }
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> &T {
+        &self.cells[row][col]
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, val: T) {
+        self.cells[row][col] = val;
+    }
+
+    /// Every (row, column) in row-major order
+    pub fn coordinates(&self) -> Vec<(usize, usize)> {
+        let n_cols = self.n_cols();
+        (0..self.n_rows()).flat_map(|row| (0..n_cols).map(move |col| (row, col))).collect()
+    }
+
+    /// The cell at an offset from (row, col), wrapping around the edges
+    pub fn wrapping_offset(&self, row: usize, col: usize, drow: isize, dcol: isize) -> (usize, usize) {
+        (
+            (row as isize + drow).rem_euclid(self.n_rows() as isize) as usize,
+            (col as isize + dcol).rem_euclid(self.n_cols() as isize) as usize,
+        )
+    }
+
+    /// The in-bounds cells touching (row, col), optionally including diagonals
+    pub fn neighbors(&self, row: usize, col: usize, diagonals: bool) -> Vec<(usize, usize)> {
+        let mut neighbors: Vec<(usize, usize)> = vec![];
+        for drow in -1isize..=1 {
+            for dcol in -1isize..=1 {
+                if (drow == 0 && dcol == 0) || (!diagonals && drow != 0 && dcol != 0) {
+                    continue
+                }
+                let (new_row, new_col) = (row as isize + drow, col as isize + dcol);
+                if new_row >= 0 && new_col >= 0 && (new_row as usize) < self.n_rows() && (new_col as usize) < self.n_cols() {
+                    neighbors.push((new_row as usize, new_col as usize));
+                }
+            }
+        }
+        neighbors
+    }
+
+    pub fn map<U: Clone, F: Fn(&T) -> U>(&self, f: F) -> Grid<U> {
+        Grid { cells: self.cells.iter().map(|row| row.iter().map(&f).collect()).collect() }
+    }
+
+    pub fn rows(&self) -> &Vec<Vec<T>> {
+        &self.cells
+    }
+
+    /// One line of text per row, drawing each cell with to_char
+    pub fn render<F: Fn(&T) -> char>(&self, to_char: F) -> String {
+        self.cells.iter().map(|row| row.iter().map(&to_char).collect::<String>()).collect::<Vec<String>>().join("\n")
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid() {
+        let grid = Grid::from_lines(vec!["123".to_string(), "456".to_string()], |c| c.to_digit(10).unwrap());
+        assert_eq!((grid.n_rows(), grid.n_cols()), (2, 3));
+        assert_eq!(*grid.get(1, 2), 6);
+        assert_eq!(grid.wrapping_offset(1, 2, 1, 1), (0, 0));
+        assert_eq!(grid.wrapping_offset(0, 0, -1, -1), (1, 2));
+        assert_eq!(grid.neighbors(0, 0, false), vec![(0, 1), (1, 0)]);
+        assert_eq!(grid.neighbors(0, 1, true).len(), 5);
+        assert_eq!(grid.map(|v| v % 2 == 0).render(|even| if *even { '#' } else { '.' }), ".#.\n#.#");
+    }
+}
@@ -0,0 +1,183 @@
+use std::cmp::{max, min};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InclusiveRange {
+    pub min: isize,
+    pub max: isize,
+}
+
+
+impl InclusiveRange {
+    pub fn new(min: isize, max: isize) -> InclusiveRange {
+        InclusiveRange { min, max }
+    }
+
+    pub fn len(&self) -> usize {
+        (self.max - self.min + 1) as usize
+    }
+
+    pub fn contains(&self, val: isize) -> bool {
+        self.min <= val && val <= self.max
+    }
+
+    pub fn contains_range(&self, other: &InclusiveRange) -> bool {
+        self.min <= other.min && other.max <= self.max
+    }
+
+    pub fn is_disjoint(&self, other: &InclusiveRange) -> bool {
+        other.min > self.max || other.max < self.min
+    }
+
+    pub fn intersection(&self, other: &InclusiveRange) -> Option<InclusiveRange> {
+        if self.is_disjoint(other) {
+            None
+        } else {
+            Some(InclusiveRange::new(max(self.min, other.min), min(self.max, other.max)))
+        }
+    }
+
+    /// The (at most two) pieces of self not covered by other, lowest first
+    pub fn subtract(&self, other: &InclusiveRange) -> Vec<InclusiveRange> {
+        match self.intersection(other) {
+            None => vec![*self],
+            Some(overlap) => {
+                let mut pieces: Vec<InclusiveRange> = vec![];
+                if self.min < overlap.min {
+                    pieces.push(InclusiveRange::new(self.min, overlap.min - 1));
+                }
+                if overlap.max < self.max {
+                    pieces.push(InclusiveRange::new(overlap.max + 1, self.max));
+                }
+                pieces
+            }
+        }
+    }
+}
+
+
+/// An axis-aligned box in N dimensions; ranges[d] is the extent along axis d
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AxisBox<const N: usize> {
+    pub ranges: [InclusiveRange; N],
+}
+
+
+impl<const N: usize> AxisBox<N> {
+    pub fn new(ranges: [InclusiveRange; N]) -> AxisBox<N> {
+        AxisBox { ranges }
+    }
+
+    pub fn volume(&self) -> usize {
+        self.ranges.iter().map(|r| r.len()).product()
+    }
+
+    pub fn contains_point(&self, point: &[isize; N]) -> bool {
+        self.ranges.iter().zip(point.iter()).all(|(r, val)| r.contains(*val))
+    }
+
+    pub fn contains(&self, other: &AxisBox<N>) -> bool {
+        self.ranges.iter().zip(other.ranges.iter()).all(|(r, o)| r.contains_range(o))
+    }
+
+    pub fn is_disjoint(&self, other: &AxisBox<N>) -> bool {
+        self.ranges.iter().zip(other.ranges.iter()).any(|(r, o)| r.is_disjoint(o))
+    }
+
+    pub fn intersection(&self, other: &AxisBox<N>) -> Option<AxisBox<N>> {
+        if self.is_disjoint(other) {
+            return None
+        }
+        let mut ranges = self.ranges;
+        for (range, o) in ranges.iter_mut().zip(other.ranges.iter()) {
+            *range = range.intersection(o).unwrap();
+        }
+        Some(AxisBox::new(ranges))
+    }
+
+    /// The part of self lying within bounds, if any
+    pub fn clip(&self, bounds: &AxisBox<N>) -> Option<AxisBox<N>> {
+        self.intersection(bounds)
+    }
+
+    /// Splits self minus other into at most 2N disjoint boxes. Each axis in
+    /// turn peels off the slabs of self lying outside other along that axis,
+    /// then narrows what remains to the overlap before moving on.
+    pub fn subtract(&self, other: &AxisBox<N>) -> Vec<AxisBox<N>> {
+        if self.is_disjoint(other) {
+            return vec![*self]
+        }
+
+        let mut pieces: Vec<AxisBox<N>> = vec![];
+        let mut remaining = *self;
+        for axis in 0..N {
+            for slab in remaining.ranges[axis].subtract(&other.ranges[axis]) {
+                let mut ranges = remaining.ranges;
+                ranges[axis] = slab;
+                pieces.push(AxisBox::new(ranges));
+            }
+            remaining.ranges[axis] = remaining.ranges[axis].intersection(&other.ranges[axis]).unwrap();
+        }
+        pieces
+    }
+}
+
+
+/// The number of lattice points covered by at least one of boxes
+pub fn union_volume<const N: usize>(boxes: &[AxisBox<N>]) -> usize {
+    let mut disjoint: Vec<AxisBox<N>> = vec![];
+    for b in boxes {
+        disjoint = disjoint.iter().flat_map(|piece| piece.subtract(b)).collect();
+        disjoint.push(*b);
+    }
+    disjoint.iter().map(|piece| piece.volume()).sum()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cube(min: isize, max: isize) -> AxisBox<3> {
+        AxisBox::new([InclusiveRange::new(min, max); 3])
+    }
+
+    #[test]
+    fn test_range_subtract() {
+        let r = InclusiveRange::new(0, 9);
+        assert_eq!(r.subtract(&InclusiveRange::new(3, 4)), vec![InclusiveRange::new(0, 2), InclusiveRange::new(5, 9)]);
+        assert_eq!(r.subtract(&InclusiveRange::new(-5, 4)), vec![InclusiveRange::new(5, 9)]);
+        assert_eq!(r.subtract(&InclusiveRange::new(-5, 15)), vec![]);
+        assert_eq!(r.subtract(&InclusiveRange::new(10, 15)), vec![r]);
+    }
+
+    #[test]
+    fn test_box_intersection_and_containment() {
+        assert_eq!(cube(10, 12).intersection(&cube(11, 13)), Some(cube(11, 12)));
+        assert_eq!(cube(0, 1).intersection(&cube(2, 2)), None);
+        assert!(cube(0, 10).contains(&cube(2, 3)));
+        assert!(!cube(2, 3).contains(&cube(0, 10)));
+        assert!(cube(0, 1).contains_point(&[1, 0, 1]));
+        assert!(!cube(0, 1).contains_point(&[1, 2, 1]));
+        assert_eq!(cube(-60, 60).clip(&cube(-50, 50)), Some(cube(-50, 50)));
+    }
+
+    #[test]
+    fn test_box_subtract() {
+        let pieces = cube(10, 12).subtract(&cube(11, 13));
+        assert_eq!(pieces.iter().map(|p| p.volume()).sum::<usize>(), 27 - 8);
+        for (idx, p) in pieces.iter().enumerate() {
+            assert!(cube(10, 12).contains(p));
+            assert!(p.is_disjoint(&cube(11, 13)));
+            assert!(pieces[idx + 1..].iter().all(|q| p.is_disjoint(q)));
+        }
+        assert_eq!(cube(0, 1).subtract(&cube(-1, 2)), vec![]);
+    }
+
+    #[test]
+    fn test_union_volume() {
+        assert_eq!(union_volume(&[cube(10, 12), cube(11, 13)]), 27 + 27 - 8);
+        assert_eq!(union_volume(&[cube(0, 9), cube(2, 3)]), 1000);
+        assert_eq!(union_volume::<3>(&[]), 0);
+    }
+}
@@ -0,0 +1,127 @@
+use std::fs;
+use std::io;
+
+use crate::utils::grid::Grid;
+
+
+/// The brightest gray level written to PGM files
+pub const MAX_GRAY: u8 = 255;
+/// Plain netpbm readers may reject longer lines
+const MAX_LINE_LENGTH: usize = 70;
+
+
+/// How numeric cell values become gray levels, from 0 (black) to MAX_GRAY (white)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Palette {
+    /// 0 is black and max (or anything above it) is white
+    Linear { max: usize },
+    /// 0 is white and max (or anything above it) is black
+    InvertedLinear { max: usize },
+    /// Value v is drawn as levels[v]; values past the end use the last level,
+    /// and with no levels at all everything is white
+    Levels(Vec<u8>),
+}
+
+
+impl Palette {
+    pub fn gray_level(&self, val: usize) -> u8 {
+        match self {
+            Palette::Linear { max } if val >= *max => MAX_GRAY,
+            Palette::Linear { max } => (val * MAX_GRAY as usize / max) as u8,
+            Palette::InvertedLinear { max } => MAX_GRAY - Palette::Linear { max: *max }.gray_level(val),
+            Palette::Levels(levels) => *levels.get(val).or(levels.last()).unwrap_or(&MAX_GRAY),
+        }
+    }
+}
+
+
+/// Each cell repeated scale times across and down
+fn scaled_rows<T: Clone>(grid: &Grid<T>, scale: usize) -> Vec<Vec<&T>> {
+    grid.rows().iter()
+        .flat_map(|row| vec![row.iter().flat_map(|cell| vec![cell; scale]).collect::<Vec<&T>>(); scale])
+        .collect()
+}
+
+
+/// Joins tokens with sep, starting a new line rather than passing MAX_LINE_LENGTH
+fn wrap(tokens: Vec<String>, sep: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.len() + sep.len() + token.len() > MAX_LINE_LENGTH {
+            lines.push(line);
+            line = String::new();
+        }
+        if !line.is_empty() {
+            line.push_str(sep);
+        }
+        line.push_str(&token);
+    }
+    lines.push(line);
+    lines
+}
+
+
+/// A plain (P1) PBM image with true cells black, each cell drawn as a scale x scale square
+pub fn to_pbm(black: &Grid<bool>, scale: usize) -> String {
+    let rows = scaled_rows(black, scale);
+    let mut lines = vec!["P1".to_string(), format!("{} {}", black.n_cols() * scale, rows.len())];
+    for row in rows {
+        lines.extend(wrap(row.iter().map(|cell| if **cell { "1" } else { "0" }.to_string()).collect(), ""));
+    }
+    lines.join("\n") + "\n"
+}
+
+
+/// A plain (P2) PGM image coloured by palette, each cell drawn as a scale x scale square
+pub fn to_pgm(values: &Grid<usize>, scale: usize, palette: &Palette) -> String {
+    let rows = scaled_rows(values, scale);
+    let mut lines = vec!["P2".to_string(), format!("{} {}", values.n_cols() * scale, rows.len()), MAX_GRAY.to_string()];
+    for row in rows {
+        lines.extend(wrap(row.iter().map(|val| palette.gray_level(**val).to_string()).collect(), " "));
+    }
+    lines.join("\n") + "\n"
+}
+
+
+pub fn write_pbm(path: &str, black: &Grid<bool>, scale: usize) -> io::Result<()> {
+    fs::write(path, to_pbm(black, scale))
+}
+
+
+pub fn write_pgm(path: &str, values: &Grid<usize>, scale: usize, palette: &Palette) -> io::Result<()> {
+    fs::write(path, to_pgm(values, scale, palette))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_pbm() {
+        let black = Grid::from_rows(vec![vec![true, false], vec![false, false]]);
+        assert_eq!(to_pbm(&black, 1), "P1\n2 2\n10\n00\n");
+        assert_eq!(to_pbm(&black, 2), "P1\n4 4\n1100\n1100\n0000\n0000\n");
+
+        let wide = Grid::new(1, 80, true);
+        assert_eq!(to_pbm(&wide, 1).lines().map(|line| line.len()).collect::<Vec<usize>>(), vec![2, 4, 70, 10]);
+    }
+
+    #[test]
+    fn test_to_pgm() {
+        let values = Grid::from_rows(vec![vec![0, 5, 9]]);
+        assert_eq!(to_pgm(&values, 1, &Palette::Linear { max: 9 }), "P2\n3 1\n255\n0 141 255\n");
+        assert_eq!(to_pgm(&values, 1, &Palette::InvertedLinear { max: 5 }), "P2\n3 1\n255\n255 0 0\n");
+        assert_eq!(to_pgm(&values, 1, &Palette::Levels(vec![10, 20])), "P2\n3 1\n255\n10 20 20\n");
+        assert_eq!(to_pgm(&values, 2, &Palette::Levels(vec![1])), "P2\n6 2\n255\n1 1 1 1 1 1\n1 1 1 1 1 1\n");
+    }
+
+    #[test]
+    fn test_palette() {
+        assert_eq!(Palette::Linear { max: 0 }.gray_level(0), MAX_GRAY);
+        assert_eq!(Palette::Linear { max: 0 }.gray_level(3), MAX_GRAY);
+        assert_eq!(Palette::Linear { max: 2 }.gray_level(1), 127);
+        assert_eq!(Palette::Levels(vec![]).gray_level(0), MAX_GRAY);
+    }
+}
@@ -0,0 +1,120 @@
+use std::fmt;
+
+use crate::utils::grid::Grid;
+
+
+pub const GLYPH_WIDTH: usize = 4;
+pub const GLYPH_HEIGHT: usize = 6;
+// one blank column between letters
+pub const GLYPH_PITCH: usize = GLYPH_WIDTH + 1;
+
+
+// the capital letters Advent of Code draws its answers in
+const FONT: [(char, [&str; GLYPH_HEIGHT]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OcrError {
+    WrongHeight { n_rows: usize },
+    UnknownGlyph { index: usize, rendering: String },
+}
+
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OcrError::WrongHeight { n_rows } => write!(f, "expected {} rows of letters, found {}", GLYPH_HEIGHT, n_rows),
+            OcrError::UnknownGlyph { index, rendering } => write!(f, "unknown glyph at letter {}:\n{}", index, rendering),
+        }
+    }
+}
+
+
+fn render_lit(lit: &bool) -> char {
+    if *lit { '#' } else { '.' }
+}
+
+
+/// Columns past the edge of lit read as unlit
+fn glyph_at(lit: &Grid<bool>, index: usize) -> Grid<bool> {
+    let mut glyph = Grid::new(GLYPH_HEIGHT, GLYPH_WIDTH, false);
+    for (row, col) in glyph.coordinates() {
+        let grid_col = index * GLYPH_PITCH + col;
+        if grid_col < lit.n_cols() {
+            glyph.set(row, col, *lit.get(row, grid_col));
+        }
+    }
+    glyph
+}
+
+
+/// Reads the letters drawn by the lit cells, left to right
+pub fn recognize(lit: &Grid<bool>) -> Result<String, OcrError> {
+    if lit.n_rows() != GLYPH_HEIGHT {
+        return Err(OcrError::WrongHeight { n_rows: lit.n_rows() })
+    }
+
+    let n_glyphs = lit.n_cols().div_ceil(GLYPH_PITCH);
+    (0..n_glyphs).map(|index| {
+        let rendering = glyph_at(lit, index).render(render_lit);
+        FONT.iter()
+            .find(|(_, rows)| rows.join("\n") == rendering)
+            .map(|(letter, _)| *letter)
+            .ok_or(OcrError::UnknownGlyph { index, rendering })
+    }).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(rows: &[&str]) -> Grid<bool> {
+        Grid::from_lines(rows.iter().map(|row| row.to_string()), |c| c == '#')
+    }
+
+    #[test]
+    fn test_recognize() {
+        let lit = grid(&[
+            "#..#...##.###..#..#.####.#..#.###...##.",
+            "#.#.....#.#..#.#.#..#....#..#.#..#.#..#",
+            "##......#.###..##...###..#..#.###..#...",
+            "#.#.....#.#..#.#.#..#....#..#.#..#.#.##",
+            "#.#..#..#.#..#.#.#..#....#..#.#..#.#..#",
+            "#..#..##..###..#..#.####..##..###...###",
+        ]);
+        assert_eq!(recognize(&lit), Ok("KJBKEUBG".to_string()));
+
+        for (letter, rows) in FONT.iter() {
+            assert_eq!(recognize(&grid(rows)), Ok(letter.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_recognize_errors() {
+        let lit = grid(&["####.#..#", "#..#.#..#", "#..#.####", "#..#.#..#", "#..#.#..#", "####.#..#"]);
+        assert_eq!(recognize(&lit), Err(OcrError::UnknownGlyph {
+            index: 0,
+            rendering: "####\n#..#\n#..#\n#..#\n#..#\n####".to_string()
+        }));
+        assert_eq!(recognize(&grid(&["####"])), Err(OcrError::WrongHeight { n_rows: 1 }));
+    }
+}
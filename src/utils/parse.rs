@@ -0,0 +1,201 @@
+use std::fmt;
+use std::str::FromStr;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref SIGNED_INT_RE: Regex = Regex::new(r"-?[0-9]+").unwrap();
+    static ref UNSIGNED_INT_RE: Regex = Regex::new(r"[0-9]+").unwrap();
+}
+
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The literal text of the template was not found where expected
+    Mismatch { line: String, literal: String, offset: usize },
+    /// The line yielded a different number of fields than the target type holds
+    FieldCount { expected: usize, found: usize },
+    /// A field was extracted but could not be parsed into the target type
+    BadField { index: usize, field: String, target: &'static str },
+}
+
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Mismatch { line, literal, offset } => write!(
+                f, "expected {:?} at offset {} of {:?}", literal, offset, line
+            ),
+            ParseError::FieldCount { expected, found } => write!(
+                f, "expected {} fields, found {}", expected, found
+            ),
+            ParseError::BadField { index, field, target } => write!(
+                f, "could not parse field {} ({:?}) as {}", index, field, target
+            ),
+        }
+    }
+}
+
+
+/// A line pattern like `"x={}..{}, y={}..{}"` where each `{}` marks a field.
+/// Fields extend up to the next literal piece of the template, so two
+/// fields must always be separated by some literal text.
+pub struct Template {
+    literals: Vec<String>,
+}
+
+
+impl Template {
+    pub fn new(pattern: &str) -> Template {
+        Template { literals: pattern.split("{}").map(|s| s.to_string()).collect() }
+    }
+
+    /// Splits line into the raw text of each field
+    pub fn fields<'a>(&self, line: &'a str) -> Result<Vec<&'a str>, ParseError> {
+        let mismatch = |literal: &String, offset: usize| ParseError::Mismatch {
+            line: line.to_string(), literal: literal.clone(), offset
+        };
+
+        let first = &self.literals[0];
+        if !line.starts_with(first.as_str()) || (self.literals.len() == 1 && line != first) {
+            return Err(mismatch(first, 0))
+        }
+
+        let mut fields: Vec<&str> = vec![];
+        let mut cursor = first.len();
+        for (idx, literal) in self.literals.iter().enumerate().skip(1) {
+            let is_last = idx == self.literals.len() - 1;
+            let field_end = if is_last {
+                if !line[cursor..].ends_with(literal.as_str()) {
+                    return Err(mismatch(literal, cursor))
+                }
+                line.len() - literal.len()
+            } else {
+                match line[cursor..].find(literal.as_str()) {
+                    Some(pos) => cursor + pos,
+                    None => return Err(mismatch(literal, cursor))
+                }
+            };
+            fields.push(&line[cursor..field_end]);
+            cursor = field_end + literal.len();
+        }
+        Ok(fields)
+    }
+
+    pub fn scan<T: FromFields>(&self, line: &str) -> Result<T, ParseError> {
+        T::from_fields(&self.fields(line)?)
+    }
+}
+
+
+/// Types that can be built from the raw fields captured by a Template
+pub trait FromFields: Sized {
+    fn from_fields(fields: &[&str]) -> Result<Self, ParseError>;
+}
+
+
+fn parse_field<T: FromStr>(fields: &[&str], index: usize) -> Result<T, ParseError> {
+    fields[index].parse::<T>().map_err(|_| ParseError::BadField {
+        index, field: fields[index].to_string(), target: std::any::type_name::<T>()
+    })
+}
+
+
+macro_rules! impl_from_fields {
+    ($n:expr; $($t:ident $idx:tt),+) => {
+        impl<$($t: FromStr),+> FromFields for ($($t,)+) {
+            fn from_fields(fields: &[&str]) -> Result<Self, ParseError> {
+                if fields.len() != $n {
+                    return Err(ParseError::FieldCount { expected: $n, found: fields.len() })
+                }
+                Ok(($(parse_field::<$t>(fields, $idx)?,)+))
+            }
+        }
+    };
+}
+
+impl_from_fields!(1; A 0);
+impl_from_fields!(2; A 0, B 1);
+impl_from_fields!(3; A 0, B 1, C 2);
+impl_from_fields!(4; A 0, B 1, C 2, D 3);
+impl_from_fields!(5; A 0, B 1, C 2, D 3, E 4);
+impl_from_fields!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+impl_from_fields!(7; A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+
+
+/// Matches line against pattern and parses each `{}` field into a tuple, e.g.
+/// `scan::<(isize, isize)>("x={}..{}", "x=-3..5") == Ok((-3, 5))`
+pub fn scan<T: FromFields>(pattern: &str, line: &str) -> Result<T, ParseError> {
+    Template::new(pattern).scan(line)
+}
+
+
+fn all_matches<T: FromStr>(re: &Regex, line: &str) -> Result<Vec<T>, ParseError> {
+    let matches: Vec<&str> = re.find_iter(line).map(|m| m.as_str()).collect();
+    (0..matches.len()).map(|idx| parse_field::<T>(&matches, idx)).collect()
+}
+
+
+/// Every (optionally negative) integer on the line, in order of appearance
+pub fn signed_ints<T: FromStr>(line: &str) -> Result<Vec<T>, ParseError> {
+    all_matches(&SIGNED_INT_RE, line)
+}
+
+
+/// Every run of digits on the line, in order of appearance; `-` is ignored
+pub fn unsigned_ints<T: FromStr>(line: &str) -> Result<Vec<T>, ParseError> {
+    all_matches(&UNSIGNED_INT_RE, line)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan() {
+        assert_eq!(
+            scan::<(isize, isize, isize, isize)>("target area: x={}..{}, y={}..{}", "target area: x=20..30, y=-10..-5"),
+            Ok((20, 30, -10, -5))
+        );
+        assert_eq!(
+            scan::<(String, isize)>("{} x={}", "off x=-4"),
+            Ok(("off".to_string(), -4))
+        );
+        assert_eq!(scan::<(usize,)>("--- scanner {} ---", "--- scanner 12 ---"), Ok((12,)));
+        assert_eq!(scan::<(char, usize)>("fold along {}={}", "fold along y=7"), Ok(('y', 7)));
+    }
+
+    #[test]
+    fn test_scan_errors() {
+        assert_eq!(
+            scan::<(usize, usize)>("{},{}", "3;4"),
+            Err(ParseError::Mismatch { line: "3;4".to_string(), literal: ",".to_string(), offset: 0 })
+        );
+        assert_eq!(
+            scan::<(usize,)>("{},{}", "3,4"),
+            Err(ParseError::FieldCount { expected: 1, found: 2 })
+        );
+        assert_eq!(
+            scan::<(usize, usize)>("{},{}", "3,-4"),
+            Err(ParseError::BadField { index: 1, field: "-4".to_string(), target: "usize" })
+        );
+        assert!(scan::<(usize,)>("a{}b", "ab-").is_err());
+
+        // a template with no fields must match the whole line
+        assert_eq!(Template::new("abc").fields("abc"), Ok(vec![]));
+        assert_eq!(
+            Template::new("abc").fields("abcdef"),
+            Err(ParseError::Mismatch { line: "abcdef".to_string(), literal: "abc".to_string(), offset: 0 })
+        );
+    }
+
+    #[test]
+    fn test_ints() {
+        assert_eq!(signed_ints::<isize>("on x=-10..12,y=10..12"), Ok(vec![-10, 12, 10, 12]));
+        assert_eq!(unsigned_ints::<usize>("0,9 -> 5,9"), Ok(vec![0, 9, 5, 9]));
+        assert_eq!(unsigned_ints::<usize>("no numbers"), Ok(vec![]));
+        assert!(signed_ints::<u8>("300").is_err());
+    }
+}
@@ -0,0 +1,23 @@
+/// Seeded linear congruential generator so randomized tests are reproducible
+pub struct Lcg(u64);
+
+
+impl Lcg {
+    pub fn new(seed: u64) -> Lcg {
+        Lcg(seed)
+    }
+
+    fn advance(&mut self) -> u64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        self.0
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.advance() >> 11
+    }
+
+    /// Uniform-ish in min..=max, drawn from the state's top 31 bits
+    pub fn next_in(&mut self, min: isize, max: isize) -> isize {
+        min + ((self.advance() >> 33) % (max - min + 1) as u64) as isize
+    }
+}